@@ -1,4 +1,6 @@
-use crate::{Name, Type, TypeSchema, Variable};
+use crate::theory::unify_args;
+use crate::types::dismantle;
+use crate::{Bounds, Cast, Name, Type, TypeSchema, Variable};
 use indexmap::IndexMap;
 use std::{cell::RefCell, collections::HashMap, error, fmt};
 
@@ -11,6 +13,10 @@ pub enum UnificationError<N: Name = &'static str> {
     /// `Failure` happens when symbols or type variants don't unify because of
     /// structural differences.
     Failure(Type<N>, Type<N>),
+    /// `Unsolvable` happens when type-level naturals may be equal, but the
+    /// equation between them is beyond what unification solves (e.g.
+    /// `t0 + t1 = 3`).
+    Unsolvable(Type<N>, Type<N>),
 }
 impl<N: Name> fmt::Display for UnificationError<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
@@ -19,6 +25,9 @@ impl<N: Name> fmt::Display for UnificationError<N> {
             UnificationError::Failure(ref t1, ref t2) => {
                 write!(f, "Failure({}, {})", t1.show(false), t2.show(false))
             }
+            UnificationError::Unsolvable(ref t1, ref t2) => {
+                write!(f, "Unsolvable({}, {})", t1.show(false), t2.show(false))
+            }
         }
     }
 }
@@ -62,20 +71,60 @@ pub struct Context<N: Name = &'static str> {
     /// [`apply`]: enum.Type.html#method.apply
     /// [`apply_mut`]: enum.Type.html#method.apply_mut
     pub(crate) path_compression_cache: RefCell<HashMap<Variable, Type<N>>>,
+    /// Subtyping constraints on [`Variable`]s, added by
+    /// [`constrain_subtype`].
+    ///
+    /// [`Variable`]: type.Variable.html
+    /// [`constrain_subtype`]: #method.constrain_subtype
+    pub(crate) bounds: IndexMap<Variable, Bounds<N>>,
+    /// Whether unification checks consistency rather than equality, set by
+    /// [`set_gradual`].
+    ///
+    /// [`set_gradual`]: #method.set_gradual
+    pub(crate) gradual: bool,
+    /// The casts gradual unification has needed.
+    pub(crate) casts: Vec<Cast<N>>,
     /// A counter used to generate fresh [`Variable`]s
     ///
     /// [`Variable`]: type.Variable.html
-    next: Variable,
+    pub(crate) next: Variable,
 }
 impl<N: Name> Default for Context<N> {
     fn default() -> Self {
         Context {
             substitution: IndexMap::new(),
             path_compression_cache: RefCell::new(HashMap::new()),
+            bounds: IndexMap::new(),
+            gradual: false,
+            casts: vec![],
             next: 0,
         }
     }
 }
+// The context may hold types of any depth, which dropping would recurse into.
+impl<N: Name> Drop for Context<N> {
+    fn drop(&mut self) {
+        let types = self.substitution.drain(..).map(|(_, t)| t);
+        let cached = self
+            .path_compression_cache
+            .get_mut()
+            .drain()
+            .map(|(_, t)| t);
+        let bounds = self
+            .bounds
+            .drain(..)
+            .flat_map(|(_, bounds)| bounds.lower.into_iter().chain(bounds.upper));
+        let casts = self
+            .casts
+            .drain(..)
+            .flat_map(|cast| vec![cast.from, cast.to]);
+        types
+            .chain(cached)
+            .chain(bounds)
+            .chain(casts)
+            .for_each(dismantle)
+    }
+}
 impl<N: Name> Context<N> {
     /// The substitution managed by the context.
     pub fn substitution(&self) -> &IndexMap<Variable, Type<N>> {
@@ -89,7 +138,7 @@ impl<N: Name> Context<N> {
     pub fn is_empty(&self) -> bool {
         self.substitution.is_empty()
     }
-    /// Clears the substitution managed by the context.
+    /// Clears the substitution and subtyping bounds managed by the context.
     ///
     /// # Examples
     ///
@@ -115,18 +164,13 @@ impl<N: Name> Context<N> {
     /// ```
     pub fn clean(&mut self) {
         self.substitution.clear();
+        self.bounds.clear();
         self.path_compression_cache.get_mut().clear();
     }
     /// Removes previous substitutions added to the `Context` until there are only `n` remaining.
     pub fn rollback(&mut self, n: usize) {
         self.path_compression_cache.get_mut().clear();
-        if n == 0 {
-            self.substitution.clear();
-        } else {
-            while n < self.substitution.len() {
-                self.substitution.pop();
-            }
-        }
+        self.substitution.truncate(n);
     }
     /// Create a new substitution for [`Type::Variable`] number `v` to the
     /// [`Type`] `t`.
@@ -226,12 +270,13 @@ impl<N: Name> Context<N> {
     /// [`UnificationError::Occurs`]: enum.UnificationError.html#variant.Occurs
     /// [`instantiate`]: enum.Type.html#method.instantiate
     pub fn unify(&mut self, t1: &Type<N>, t2: &Type<N>) -> Result<(), UnificationError<N>> {
-        let rollback_n = self.substitution.len();
+        let (rollback_n, casts_n) = (self.substitution.len(), self.casts.len());
         let t1 = t1.apply(self);
         let t2 = t2.apply(self);
         let result = self.unify_internal(t1, t2);
         if result.is_err() {
             self.rollback(rollback_n);
+            self.casts.truncate(casts_n);
         }
         result
     }
@@ -250,45 +295,57 @@ impl<N: Name> Context<N> {
     }
     /// unify_internal may mutate the context even with an error. The context on
     /// which it's called should be discarded if there's an error.
+    ///
+    /// Pairs of types still to unify are kept on a stack rather than
+    /// recursed into, so that types of any depth can be unified.
     fn unify_internal(&mut self, t1: Type<N>, t2: Type<N>) -> Result<(), UnificationError<N>> {
-        if t1 == t2 {
-            return Ok(());
-        }
-        match (t1, t2) {
-            (Type::Variable(v), t2) => {
-                if t2.occurs(v) {
-                    Err(UnificationError::Occurs(v))
-                } else {
-                    self.extend(v, t2);
+        let mut pending = vec![(t1, t2)];
+        while let Some((t1, t2)) = pending.pop() {
+            // earlier pairs may have constrained variables in these types.
+            let (t1, t2) = (self.resolve(t1), self.resolve(t2));
+            let result = match (t1, t2) {
+                (t1, t2) if self.gradual && (t1.is_dynamic() || t2.is_dynamic()) => {
+                    self.cast(t2, t1);
                     Ok(())
                 }
-            }
-            (t1, Type::Variable(v)) => {
-                if t1.occurs(v) {
-                    Err(UnificationError::Occurs(v))
-                } else {
-                    self.extend(v, t1);
-                    Ok(())
+                (Type::Variable(v1), Type::Variable(v2)) if v1 == v2 => Ok(()),
+                (Type::Variable(v), mut t) | (mut t, Type::Variable(v)) => {
+                    t.apply_mut(self);
+                    if t.occurs(v) {
+                        dismantle(t);
+                        Err(UnificationError::Occurs(v))
+                    } else {
+                        self.extend(v, t);
+                        Ok(())
+                    }
                 }
-            }
-            (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => {
-                if n1 != n2 {
-                    Err(UnificationError::Failure(
-                        Type::Constructed(n1, a1),
-                        Type::Constructed(n2, a2),
-                    ))
-                } else {
-                    for (mut t1, mut t2) in a1.into_iter().zip(a2) {
+                (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => {
+                    if n1 != n2 {
+                        let mut t1 = Type::Constructed(n1, a1);
+                        let mut t2 = Type::Constructed(n2, a2);
                         t1.apply_mut(self);
                         t2.apply_mut(self);
-                        self.unify_internal(t1, t2)?;
+                        Err(UnificationError::Failure(t1, t2))
+                    } else {
+                        unify_args(self, n1, a1, a2, &mut pending)
                     }
-                    Ok(())
                 }
-            }
+            };
+            result?;
         }
+        Ok(())
     }
-    /// Confines the substitution to those which act on the given variables.
+    /// If `t` is a variable with a substitution, the substituted type.
+    pub(crate) fn resolve(&self, t: Type<N>) -> Type<N> {
+        match t {
+            Type::Variable(v) if self.substitution.contains_key(&v) => t.apply(self),
+            t => t,
+        }
+    }
+    /// Confines the substitution to those which act on the given variables,
+    /// along with their subtyping [`Bounds`]. Kept substitutions and bounds
+    /// are fully applied, so they do not refer to substitutions which are
+    /// dropped, and variables with no substitution are ignored.
     ///
     /// # Examples
     ///
@@ -312,20 +369,44 @@ impl<N: Name> Context<N> {
     /// let sub = ctx.substitution();
     /// assert_eq!(sub.len(), 1);
     /// assert_eq!(sub[&1], tp!(bool));
+    ///
+    /// // v2 has no substitution
+    /// ctx.confine(&[1, 2]);
+    /// assert_eq!(ctx.substitution().len(), 1);
     /// ```
+    ///
+    /// [`Bounds`]: struct.Bounds.html
     pub fn confine(&mut self, keep: &[Variable]) {
         let mut substitution = IndexMap::new();
-        for v in keep {
-            substitution.insert(*v, self.substitution[v].clone());
+        let mut bounds = IndexMap::new();
+        for &v in keep {
+            if self.substitution.contains_key(&v) {
+                substitution.insert(v, Type::Variable(v).apply(self));
+            }
+            if let Some(b) = self.bounds.get(&v) {
+                let b = Bounds {
+                    lower: b.lower.iter().map(|t| t.apply(self)).collect(),
+                    upper: b.upper.iter().map(|t| t.apply(self)).collect(),
+                };
+                bounds.insert(v, b);
+            }
         }
+        self.clean();
         self.substitution = substitution;
+        self.bounds = bounds;
     }
     /// Merge two type contexts.
     ///
     /// Every [`Type`] ([`TypeSchema`]) that corresponds to the `other` context
     /// must be reified using [`ContextChange::reify_type`]
     /// ([`ContextChange::reify_typeschema`]). Any [`Variable`] in `sacreds`
-    /// will not be changed by the context (i.e. reification will ignore it).
+    /// will not be changed by the context (i.e. reification will ignore it),
+    /// and is the same variable in both contexts: its constraints in `other`
+    /// are unified with those in this context. If they conflict, the
+    /// [`UnificationError`] is returned and this context is left unaffected.
+    /// The subtyping [`Bounds`] of `other` are dropped rather than merged, as
+    /// checking them against this context's bounds would need a
+    /// [`SubtypeLattice`].
     ///
     /// # Examples
     ///
@@ -347,7 +428,7 @@ impl<N: Name> Context<N> {
     /// assert_eq!(t.apply(&ctx2).to_string(), "bool → t1");
     /// // ctx2 uses t0 and t1
     ///
-    /// let ctx_change = ctx.merge(ctx2, vec![]);
+    /// let ctx_change = ctx.merge(ctx2, vec![]).expect("no conflict");
     /// // rewrite all terms under ctx2 using ctx_change
     /// ctx_change.reify_type(&mut t);
     /// assert_eq!(t.to_string(), "t2 → t3");
@@ -376,7 +457,7 @@ impl<N: Name> Context<N> {
     /// // ctx2 uses t0 and t1
     ///
     /// // t1 from ctx2 is preserved *and* constrained by ctx
-    /// let ctx_change = ctx.merge(ctx2, vec![1]);
+    /// let ctx_change = ctx.merge(ctx2, vec![1]).expect("no conflict");
     /// // rewrite all terms under ctx2 using ctx_change
     /// ctx_change.reify_type(&mut t);
     /// assert_eq!(t.to_string(), "t2 → t1");
@@ -384,54 +465,345 @@ impl<N: Name> Context<N> {
     ///
     /// assert_eq!(ctx.new_variable(), tp!(4));
     /// ```
+    ///
+    /// Constraints on sacred variables must agree:
+    ///
+    /// ```
+    /// # use polytype::{Context, UnificationError, tp};
+    /// let mut ctx = Context::default();
+    /// let a = ctx.new_variable();
+    /// ctx.unify(&a, &tp!(list(tp!(int)))).unwrap();
+    ///
+    /// let mut ctx2 = Context::default();
+    /// let a = ctx2.new_variable();
+    /// let b = ctx2.new_variable();
+    /// ctx2.unify(&a, &tp!(list(b.clone()))).unwrap();
+    ///
+    /// // t0 is list(int) in ctx, so t1 from ctx2 becomes int
+    /// let ctx_change = ctx.merge(ctx2.clone(), vec![0]).expect("no conflict");
+    /// let mut b2 = b.clone();
+    /// ctx_change.reify_type(&mut b2);
+    /// assert_eq!(b2.apply(&ctx), tp!(int));
+    ///
+    /// ctx2.unify(&b, &tp!(bool)).unwrap();
+    /// assert_eq!(
+    ///     ctx.merge(ctx2, vec![0]).err(),
+    ///     Some(UnificationError::Failure(tp!(int), tp!(bool))),
+    /// );
+    /// ```
     /// [`ContextChange::reify_type`]: struct.ContextChange.html#method.reify_type
     /// [`ContextChange::reify_typeschema`]: struct.ContextChange.html#method.reify_typeschema
     /// [`Type`]: enum.Type.html
     /// [`TypeSchema`]: enum.TypeSchema.html
     /// [`Variable`]: type.TypeSchema.html
-    pub fn merge(&mut self, other: Context<N>, sacreds: Vec<Variable>) -> ContextChange {
-        let delta = self.next;
-        for (v, tp) in other.substitution {
-            self.substitution.insert(delta + v, tp);
-        }
+    /// [`UnificationError`]: enum.UnificationError.html
+    /// [`Bounds`]: struct.Bounds.html
+    /// [`SubtypeLattice`]: struct.SubtypeLattice.html
+    pub fn merge(
+        &mut self,
+        other: Context<N>,
+        sacreds: Vec<Variable>,
+    ) -> Result<ContextChange, UnificationError<N>> {
+        let change = ContextChange {
+            delta: self.next,
+            sacreds,
+            shared: 0,
+        };
+        let next = self.next + other.next;
+        self.absorb(other, &change, |_| false)?;
         // this is intentionally wasting variable space when there are sacreds:
-        self.next += other.next;
-        ContextChange { delta, sacreds }
+        self.next = next;
+        Ok(change)
+    }
+    /// Unify each constraint of `other`, reified by `change`, into this
+    /// context, except those on variables for which `skip` holds, and carry
+    /// over its casts. On failure, this context is left unaffected; on
+    /// success, the caller should update `next`.
+    fn absorb<F: Fn(Variable) -> bool>(
+        &mut self,
+        mut other: Context<N>,
+        change: &ContextChange,
+        skip: F,
+    ) -> Result<(), UnificationError<N>> {
+        let (rollback_n, casts, next) = (self.substitution.len(), self.casts.len(), self.next);
+        for (v, mut tp) in std::mem::take(&mut other.substitution) {
+            if skip(v) {
+                continue;
+            }
+            change.reify_type(&mut tp);
+            let v = Type::Variable(change.reify_variable(v));
+            if let Err(e) = self.unify(&v, &tp) {
+                self.rollback(rollback_n);
+                self.casts.truncate(casts);
+                self.next = next;
+                return Err(e);
+            }
+        }
+        self.casts
+            .extend(std::mem::take(&mut other.casts).into_iter().map(
+                |Cast { mut from, mut to }| {
+                    change.reify_type(&mut from);
+                    change.reify_type(&mut to);
+                    Cast { from, to }
+                },
+            ));
+        Ok(())
+    }
+    /// Convert the context to use another kind of [`Name`], renaming every
+    /// constructor with `f`. See [`Type::map_name`].
+    ///
+    /// [`Name`]: trait.Name.html
+    /// [`Type::map_name`]: enum.Type.html#method.map_name
+    pub fn map_name<M: Name, F: FnMut(&N) -> M>(&self, mut f: F) -> Context<M> {
+        Context {
+            substitution: self
+                .substitution
+                .iter()
+                .map(|(v, t)| (*v, t.map_name(&mut f)))
+                .collect(),
+            path_compression_cache: RefCell::new(HashMap::new()),
+            bounds: self
+                .bounds
+                .iter()
+                .map(|(v, bounds)| {
+                    let bounds = Bounds {
+                        lower: bounds.lower.iter().map(|t| t.map_name(&mut f)).collect(),
+                        upper: bounds.upper.iter().map(|t| t.map_name(&mut f)).collect(),
+                    };
+                    (*v, bounds)
+                })
+                .collect(),
+            gradual: self.gradual,
+            casts: self
+                .casts
+                .iter()
+                .map(|cast| Cast {
+                    from: cast.from.map_name(&mut f),
+                    to: cast.to.map_name(&mut f),
+                })
+                .collect(),
+            next: self.next,
+        }
+    }
+    /// Freeze the context into a [`SolvedContext`], which can be shared
+    /// between threads.
+    ///
+    /// [`SolvedContext`]: struct.SolvedContext.html
+    pub fn solved(&self) -> SolvedContext<N> {
+        let substitution = self
+            .substitution
+            .iter()
+            .map(|(v, t)| (*v, t.apply(self)))
+            .collect();
+        SolvedContext {
+            substitution,
+            gradual: self.gradual,
+            next: self.next,
+        }
+    }
+    /// Merge a context made by [`SolvedContext::fork`] back into this one,
+    /// which must descend from the same `base`.
+    ///
+    /// Constraints the fork placed on variables of `base` are unified with
+    /// this context's, and variables the fork created are renumbered after
+    /// this context's. Every [`Type`] ([`TypeSchema`]) from the fork must be
+    /// reified using the returned [`ContextChange`]. If the constraints
+    /// conflict, this context is left unaffected.
+    ///
+    /// See [`SolvedContext`] for an example.
+    ///
+    /// [`SolvedContext::fork`]: struct.SolvedContext.html#method.fork
+    /// [`SolvedContext`]: struct.SolvedContext.html
+    /// [`Type`]: enum.Type.html
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`ContextChange`]: struct.ContextChange.html
+    pub fn merge_fork(
+        &mut self,
+        fork: Context<N>,
+        base: &SolvedContext<N>,
+    ) -> Result<ContextChange, UnificationError<N>> {
+        let change = ContextChange {
+            delta: self.next.max(base.next) - base.next,
+            sacreds: vec![],
+            shared: base.next,
+        };
+        let next = self.next.max(base.next) + fork.next.max(base.next) - base.next;
+        self.absorb(fork, &change, |v| base.substitution.contains_key(&v))?;
+        self.next = next;
+        Ok(change)
+    }
+}
+
+/// A [`Context`] whose substitution has been fully applied, so that it can
+/// [`apply`] to types without path compression. Unlike a `Context`, it is
+/// `Sync` and can be shared between threads.
+///
+/// Workers which need to unify take a [`fork`] of it, which is an ordinary
+/// `Context`, and their results are merged back with
+/// [`Context::merge_fork`].
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{tp, Context};
+/// let mut ctx = Context::default();
+/// let a = ctx.new_variable();
+/// let b = ctx.new_variable();
+/// ctx.unify(&a, &tp!(list(tp!(1)))).expect("unifies");
+/// let solved = ctx.solved();
+///
+/// let (first, second) = std::thread::scope(|s| {
+///     let first = s.spawn(|| {
+///         let mut fork = solved.fork();
+///         let x = fork.new_variable();
+///         fork.unify(&tp!(@arrow[x.clone(), b.clone()]), &tp!(@arrow[tp!(bool), tp!(int)]))
+///             .expect("unifies");
+///         (fork, x)
+///     });
+///     let second = s.spawn(|| {
+///         let mut fork = solved.fork();
+///         fork.unify(&a, &tp!(list(tp!(bool)))).expect("unifies");
+///         fork
+///     });
+///     (first.join().unwrap(), second.join().unwrap())
+/// });
+///
+/// let (fork, mut x) = first;
+/// let change = ctx.merge_fork(fork, &solved).expect("no conflict");
+/// change.reify_type(&mut x);
+/// assert_eq!(x.apply(&ctx), tp!(bool));
+/// assert_eq!(a.apply(&ctx), tp!(list(tp!(int))));
+///
+/// // the second worker needed t1 to be bool, but the first made it an int
+/// assert!(ctx.merge_fork(second, &solved).is_err());
+/// ```
+///
+/// [`Context`]: struct.Context.html
+/// [`apply`]: #method.apply
+/// [`fork`]: #method.fork
+/// [`Context::merge_fork`]: struct.Context.html#method.merge_fork
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolvedContext<N: Name = &'static str> {
+    substitution: IndexMap<Variable, Type<N>>,
+    gradual: bool,
+    next: Variable,
+}
+impl<N: Name> Drop for SolvedContext<N> {
+    fn drop(&mut self) {
+        self.substitution.drain(..).for_each(|(_, t)| dismantle(t))
+    }
+}
+impl<N: Name> SolvedContext<N> {
+    /// The substitution, in which no type mentions a substituted variable.
+    pub fn substitution(&self) -> &IndexMap<Variable, Type<N>> {
+        &self.substitution
+    }
+    /// The number of constraints in the substitution.
+    pub fn len(&self) -> usize {
+        self.substitution.len()
+    }
+    /// `true` if the substitution has any constraints, else `false`.
+    pub fn is_empty(&self) -> bool {
+        self.substitution.is_empty()
+    }
+    /// Like [`Type::apply`], using the solved substitution.
+    ///
+    /// [`Type::apply`]: enum.Type.html#method.apply
+    pub fn apply(&self, t: &Type<N>) -> Type<N> {
+        let mut t = t.clone();
+        self.apply_mut(&mut t);
+        t
+    }
+    /// Like [`apply`], but works in-place.
+    ///
+    /// [`apply`]: #method.apply
+    pub fn apply_mut(&self, t: &mut Type<N>) {
+        let mut stack = vec![t];
+        while let Some(t) = stack.pop() {
+            match *t {
+                Type::Constructed(_, ref mut args) => stack.extend(args),
+                Type::Variable(v) => {
+                    if let Some(tp) = self.substitution.get(&v) {
+                        *t = tp.clone()
+                    }
+                }
+            }
+        }
+    }
+    /// A [`Context`] with the same substitution, for a worker to unify in.
+    /// It is gradual if the context this was solved from was, but starts
+    /// without casts.
+    ///
+    /// The substitution is copied, so forking takes time linear in its size.
+    /// Searches which fork many short-lived contexts from a large one may
+    /// prefer a [`PersistentContext`], from the `persistent` feature, whose
+    /// clones share structure and take constant time.
+    ///
+    /// [`Context`]: struct.Context.html
+    /// [`PersistentContext`]: struct.PersistentContext.html
+    pub fn fork(&self) -> Context<N> {
+        Context {
+            substitution: self.substitution.clone(),
+            path_compression_cache: RefCell::new(HashMap::new()),
+            bounds: IndexMap::new(),
+            gradual: self.gradual,
+            casts: vec![],
+            next: self.next,
+        }
+    }
+}
+impl<N: Name> From<Context<N>> for SolvedContext<N> {
+    fn from(ctx: Context<N>) -> Self {
+        ctx.solved()
     }
 }
 
 /// Allow types to be reified for use in a different context. See [`Context::merge`].
 ///
+/// Types and schemas may use any kind of [`Name`].
+///
+/// [`Name`]: trait.Name.html
 /// [`Context::merge`]: struct.Context.html#method.merge
 pub struct ContextChange {
     delta: usize,
     sacreds: Vec<Variable>,
+    /// Variables below this are shared by both contexts and left unchanged.
+    shared: Variable,
 }
 impl ContextChange {
     /// Reify a [`Type`] for use under a merged [`Context`].
     ///
     /// [`Type`]: enum.Type.html
     /// [`Context`]: struct.Context.html
-    pub fn reify_type(&self, tp: &mut Type) {
-        match tp {
-            Type::Constructed(_, args) => {
-                for arg in args {
-                    self.reify_type(arg)
-                }
+    pub fn reify_type<N: Name>(&self, tp: &mut Type<N>) {
+        let mut stack = vec![tp];
+        while let Some(tp) = stack.pop() {
+            match tp {
+                Type::Constructed(_, args) => stack.extend(args),
+                Type::Variable(n) => *n = self.reify_variable(*n),
             }
-            Type::Variable(n) if self.sacreds.contains(n) => (),
-            Type::Variable(n) => *n += self.delta,
+        }
+    }
+    /// Reify a [`Variable`] for use under a merged [`Context`].
+    ///
+    /// [`Variable`]: type.Variable.html
+    /// [`Context`]: struct.Context.html
+    pub fn reify_variable(&self, v: Variable) -> Variable {
+        if v < self.shared || self.sacreds.contains(&v) {
+            v
+        } else {
+            v + self.delta
         }
     }
     /// Reify a [`TypeSchema`] for use under a merged [`Context`].
     ///
     /// [`TypeSchema`]: enum.TypeSchema.html
     /// [`Context`]: struct.Context.html
-    pub fn reify_typeschema(&self, tpsc: &mut TypeSchema) {
+    pub fn reify_typeschema<N: Name>(&self, tpsc: &mut TypeSchema<N>) {
         match tpsc {
             TypeSchema::Monotype(tp) => self.reify_type(tp),
             TypeSchema::Polytype { variable, body } => {
-                *variable += self.delta;
+                *variable = self.reify_variable(*variable);
                 self.reify_typeschema(body);
             }
         }
@@ -0,0 +1,208 @@
+#[cfg(feature = "parser")]
+use crate::ParseError;
+use crate::{Name, Type, TypeSchema, Variable};
+
+/// A declaration of an algebraic data type, such as
+/// `data maybe(a) = none | some(a)`, from which the schemas of its
+/// constructors and eliminators are generated.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, DataConstructor, DataDecl};
+/// // data list(a) = nil | cons(a, list(a))
+/// let list = DataDecl {
+///     name: "list",
+///     params: vec![0],
+///     constructors: vec![
+///         DataConstructor { name: "nil".into(), fields: vec![] },
+///         DataConstructor { name: "cons".into(), fields: vec![tp!(0), tp!(list(tp!(0)))] },
+///     ],
+/// };
+/// assert_eq!(list.arity(), 1);
+/// assert_eq!(list.constructor_schemas(), vec![
+///     ("nil".to_string(), ptp!(0; list(tp!(0)))),
+///     ("cons".to_string(), ptp!(0; @arrow[tp!(0), tp!(list(tp!(0))), tp!(list(tp!(0)))])),
+/// ]);
+/// assert_eq!(
+///     list.eliminator().to_string(),
+///     "∀t0. ∀t1. list(t0) → t1 → (t0 → list(t0) → t1) → t1",
+/// );
+/// assert_eq!(
+///     list.fold().to_string(),
+///     "∀t0. ∀t1. list(t0) → t1 → (t0 → t1 → t1) → t1",
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataDecl<N: Name = &'static str> {
+    /// The name of the type.
+    pub name: N,
+    /// The parameters of the type, in order.
+    pub params: Vec<Variable>,
+    /// The constructors of the type, in order.
+    pub constructors: Vec<DataConstructor<N>>,
+}
+
+/// A constructor of a [`DataDecl`], such as `some(a)`.
+///
+/// [`DataDecl`]: struct.DataDecl.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataConstructor<N: Name = &'static str> {
+    /// The name of the constructor.
+    pub name: String,
+    /// The types of the constructor's fields, over the parameters of the type.
+    pub fields: Vec<Type<N>>,
+}
+
+impl<N: Name> DataDecl<N> {
+    /// Parse a declaration such as `data list(a) = nil | cons(a, list(a))`.
+    /// The parameters are numbered from `0` in order, and fields may only
+    /// use variables by their parameter names.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, DataDecl};
+    /// let either: DataDecl = DataDecl::parse("data either(a, b) = left(a) | right(b)")
+    ///     .expect("valid declaration");
+    /// assert_eq!(either.params, vec![0, 1]);
+    /// assert_eq!(either.constructors[1].name, "right");
+    /// assert_eq!(either.constructors[1].fields, vec![tp!(1)]);
+    /// assert!(DataDecl::<&'static str>::parse("data maybe(a) = none | some(t0)").is_err());
+    /// ```
+    #[cfg(feature = "parser")]
+    pub fn parse(s: &str) -> Result<DataDecl<N>, ParseError> {
+        let rest = crate::module::keyword(s.trim(), "data").ok_or(ParseError)?;
+        let mut parts = rest.splitn(2, '=');
+        let (head, body) = (parts.next().unwrap(), parts.next().ok_or(ParseError)?);
+        let (name, params) = crate::module::head_of(head).map_err(|_| ParseError)?;
+        let vars: Vec<Variable> = (0..params.len()).collect();
+        let constructors = body
+            .split('|')
+            .map(|c| {
+                let c = c.trim();
+                let t = if params.is_empty() {
+                    Type::<N>::parse(c)?
+                } else {
+                    let schema = format!("∀{}. {}", params.join(" "), c);
+                    body_of(TypeSchema::parse_named(&schema)?.0, &vars)?
+                };
+                match t {
+                    Type::Constructed(n, _) if n.is_arrow() => Err(ParseError),
+                    Type::Constructed(n, fields) if fields.iter().all(|f| within(f, &vars)) => {
+                        Ok(DataConstructor {
+                            name: n.show(),
+                            fields,
+                        })
+                    }
+                    _ => Err(ParseError),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(DataDecl {
+            name: N::parse(&name)?,
+            params: vars,
+            constructors,
+        })
+    }
+    /// The number of parameters of the type.
+    pub fn arity(&self) -> usize {
+        self.params.len()
+    }
+    /// The type applied to its parameters, e.g. `maybe(t0)`.
+    pub fn tp(&self) -> Type<N> {
+        let params = self.params.iter().map(|&v| Type::Variable(v)).collect();
+        Type::Constructed(self.name.clone(), params)
+    }
+    /// The schema of each constructor, in order: a function from its fields
+    /// to the type, quantified over the parameters, e.g. `∀t0. t0 → maybe(t0)`
+    /// for `some`.
+    pub fn constructor_schemas(&self) -> Vec<(String, TypeSchema<N>)> {
+        self.constructors
+            .iter()
+            .map(|c| {
+                let mut tps = c.fields.clone();
+                tps.push(self.tp());
+                (c.name.clone(), self.quantify(Type::from(tps), None))
+            })
+            .collect()
+    }
+    /// The schema of the eliminator, which takes a value of the type and a
+    /// case for each constructor, in order, from its fields to a result,
+    /// e.g. `∀t0. ∀t1. maybe(t0) → t1 → (t0 → t1) → t1`.
+    pub fn eliminator(&self) -> TypeSchema<N> {
+        self.eliminate(false)
+    }
+    /// The schema of the fold, which is the [`eliminator`] but for fields of
+    /// the type itself, whose cases take the result of folding them instead,
+    /// e.g. `∀t0. ∀t1. list(t0) → t1 → (t0 → t1 → t1) → t1`. Only fields
+    /// which are exactly the type applied to its parameters are folded.
+    ///
+    /// [`eliminator`]: #method.eliminator
+    pub fn fold(&self) -> TypeSchema<N> {
+        self.eliminate(true)
+    }
+    fn eliminate(&self, fold: bool) -> TypeSchema<N> {
+        let tp = self.tp();
+        let result = self
+            .params
+            .iter()
+            .cloned()
+            .chain(
+                self.constructors
+                    .iter()
+                    .flat_map(|c| c.fields.iter())
+                    .flat_map(Type::vars),
+            )
+            .max()
+            .map_or(0, |v| v + 1);
+        let mut tps = vec![tp.clone()];
+        for c in &self.constructors {
+            let mut case: Vec<_> = c
+                .fields
+                .iter()
+                .map(|f| {
+                    if fold && *f == tp {
+                        Type::Variable(result)
+                    } else {
+                        f.clone()
+                    }
+                })
+                .collect();
+            case.push(Type::Variable(result));
+            tps.push(Type::from(case));
+        }
+        tps.push(Type::Variable(result));
+        self.quantify(Type::from(tps), Some(result))
+    }
+    /// Quantify `t` over the parameters and then `extra`.
+    fn quantify(&self, t: Type<N>, extra: Option<Variable>) -> TypeSchema<N> {
+        self.params.iter().cloned().chain(extra).rev().fold(
+            TypeSchema::Monotype(t),
+            |body, variable| TypeSchema::Polytype {
+                variable,
+                body: Box::new(body),
+            },
+        )
+    }
+}
+
+/// The body of `schema`, which must be quantified over exactly `vars`.
+#[cfg(feature = "parser")]
+fn body_of<N: Name>(mut schema: TypeSchema<N>, vars: &[Variable]) -> Result<Type<N>, ParseError> {
+    if schema.bound_vars() != vars {
+        return Err(ParseError);
+    }
+    loop {
+        match schema {
+            TypeSchema::Monotype(t) => return Ok(t),
+            TypeSchema::Polytype { body, .. } => schema = *body,
+        }
+    }
+}
+
+/// Whether every variable of `t` is among `vars`.
+#[cfg(feature = "parser")]
+fn within<N: Name>(t: &Type<N>, vars: &[Variable]) -> bool {
+    t.vars().iter().all(|v| vars.contains(v))
+}
@@ -0,0 +1,217 @@
+use crate::theory::{Pairs, Unifier};
+use crate::{Name, Type, UnificationError, Variable};
+
+impl<N: Name> Type<N> {
+    /// Construct a function type with side effects (i.e. `alpha →{effects}
+    /// beta`), where `effects` is an effect row made with [`Type::row`]. If
+    /// the row is empty, this is the pure [`Type::arrow`].
+    ///
+    /// Effect rows are unified regardless of the order of their labels, and a
+    /// pure arrow unifies with an arrow whose effects are empty. Row
+    /// variables are ordinary [`Type::Variable`]s, so a [`TypeSchema`] may
+    /// quantify over effects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp, Context, Type};
+    /// // map: ∀α β ε. (α →{ε} β) → list(α) →{ε} list(β)
+    /// let map = ptp!(0, 1, 2; @arrow[
+    ///     Type::effect_arrow(tp!(0), tp!(2), tp!(1)),
+    ///     Type::effect_arrow(tp!(list(tp!(0))), tp!(2), tp!(list(tp!(1)))),
+    /// ]);
+    /// assert_eq!(map.to_string(), "∀t0. ∀t1. ∀t2. (t0 →{t2} t1) → list(t0) →{t2} list(t1)");
+    ///
+    /// let mut ctx = Context::default();
+    /// let map = map.instantiate(&mut ctx);
+    /// let read = Type::effect_arrow(tp!(str), Type::row(vec![tp!(io)], None), tp!(int));
+    /// let ret = ctx.new_variable();
+    /// ctx.unify(&map, &tp!(@arrow[read, ret.clone()])).expect("unifies");
+    /// assert_eq!(ret.apply(&ctx).to_string(), "list(str) →{io} list(int)");
+    ///
+    /// // pure functions have no effects
+    /// let pure = Type::effect_arrow(tp!(int), Type::row(vec![], None), tp!(int));
+    /// assert_eq!(pure, tp!(@arrow[tp!(int), tp!(int)]));
+    /// ```
+    ///
+    /// [`Type::row`]: #method.row
+    /// [`Type::arrow`]: #method.arrow
+    /// [`Type::Variable`]: #variant.Variable
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    pub fn effect_arrow(alpha: Type<N>, effects: Type<N>, beta: Type<N>) -> Type<N> {
+        match effects {
+            Type::Constructed(ref name, ref args) if args.is_empty() && is_effects(name) => {
+                Type::arrow(alpha, beta)
+            }
+            effects => Type::Constructed(N::arrow(), vec![alpha, beta, effects]),
+        }
+    }
+    /// Construct an effect row (e.g. `{io, rand | ε}`) from its labels, and a
+    /// `tail` (usually a variable) standing for further effects, if any.
+    /// Labels are told apart by their constructor.
+    ///
+    /// # Panics
+    ///
+    /// If the [`Name`] has no [`effects`] constructor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Type};
+    /// let row = Type::row(vec![tp!(io), tp!(fail(tp!(str)))], Some(tp!(0)));
+    /// assert_eq!(row.to_string(), "{io,fail(str) | t0}");
+    /// let (labels, tail) = row.as_row().unwrap();
+    /// assert_eq!(labels, vec![&tp!(io), &tp!(fail(tp!(str)))]);
+    /// assert_eq!(tail, Some(&tp!(0)));
+    /// ```
+    ///
+    /// [`Name`]: trait.Name.html
+    /// [`effects`]: trait.Name.html#method.effects
+    pub fn row(labels: Vec<Type<N>>, tail: Option<Type<N>>) -> Type<N> {
+        Type::try_row(labels, tail).expect("the name has no constructor for effect rows")
+    }
+    pub(crate) fn try_row(labels: Vec<Type<N>>, tail: Option<Type<N>>) -> Option<Type<N>> {
+        let name = N::effects()?;
+        let tail = tail.unwrap_or_else(|| Type::Constructed(name.clone(), vec![]));
+        Some(extend_row(labels, tail))
+    }
+    /// If the type is an arrow with effects, its effect row.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Type};
+    /// let t = Type::effect_arrow(tp!(int), tp!(0), tp!(str));
+    /// assert_eq!(t.effects(), Some(&tp!(0)));
+    /// assert_eq!(tp!(@arrow[tp!(int), tp!(str)]).effects(), None);
+    /// ```
+    pub fn effects(&self) -> Option<&Type<N>> {
+        match *self {
+            Type::Constructed(ref n, ref args) if n.is_arrow() && args.len() > 2 => Some(&args[2]),
+            _ => None,
+        }
+    }
+    /// If the type is an effect row, its labels, and its tail if it is open.
+    #[allow(clippy::type_complexity)]
+    pub fn as_row(&self) -> Option<(Vec<&Type<N>>, Option<&Type<N>>)> {
+        let mut labels = vec![];
+        let mut row = self;
+        loop {
+            match *row {
+                Type::Constructed(ref n, ref args) if is_effects(n) => match args.len() {
+                    0 => return Some((labels, None)),
+                    2 => {
+                        labels.push(&args[0]);
+                        row = &args[1];
+                    }
+                    _ => return None,
+                },
+                _ if labels.is_empty() => return None,
+                _ => return Some((labels, Some(row))),
+            }
+        }
+    }
+}
+
+pub(crate) fn is_effects<N: Name>(name: &N) -> bool {
+    N::effects().as_ref() == Some(name)
+}
+
+/// `row` with `labels` added in front.
+fn extend_row<N: Name>(labels: Vec<Type<N>>, row: Type<N>) -> Type<N> {
+    let name = match N::effects() {
+        Some(name) => name,
+        None => return row,
+    };
+    labels.into_iter().rev().fold(row, |row, label| {
+        Type::Constructed(name.clone(), vec![label, row])
+    })
+}
+
+/// Whether two labels of effect rows are the same effect, as told by their
+/// constructors.
+fn same_label<N: Name>(l1: &Type<N>, l2: &Type<N>) -> bool {
+    match (l1, l2) {
+        (Type::Constructed(n1, _), Type::Constructed(n2, _)) => n1 == n2,
+        (Type::Variable(v1), Type::Variable(v2)) => v1 == v2,
+        _ => false,
+    }
+}
+
+/// The pairs of types which must unify for the rows `name(a1…)` and
+/// `name(a2…)` to unify, rewriting the second so its first label matches.
+pub(crate) fn unify_rows<N: Name, U: Unifier<N>>(
+    ctx: &mut U,
+    name: N,
+    mut a1: Vec<Type<N>>,
+    a2: Vec<Type<N>>,
+) -> Result<Pairs<N>, UnificationError<N>> {
+    if a1.len() != a2.len() {
+        return Err(UnificationError::Failure(
+            ctx.apply(&Type::Constructed(name.clone(), a1)),
+            ctx.apply(&Type::Constructed(name, a2)),
+        ));
+    }
+    if a1.len() == 2 && !same_label(&a1[0], &a2[0]) {
+        let rest1 = a1.pop().unwrap();
+        let label1 = a1.pop().unwrap();
+        let tail1 = row_tail(ctx, &rest1);
+        let (label2, rest2) = rewrite_row(ctx, Type::Constructed(name, a2), &label1, tail1)?;
+        return Ok(vec![(label1, label2), (rest1, rest2)]);
+    }
+    Ok(a1.into_iter().zip(a2).collect())
+}
+
+/// The variable ending `row`, if it is open.
+fn row_tail<N: Name, U: Unifier<N>>(ctx: &U, row: &Type<N>) -> Option<Variable> {
+    match ctx.apply(row) {
+        Type::Variable(v) => Some(v),
+        row => match row.as_row() {
+            Some((_, Some(&Type::Variable(v)))) => Some(v),
+            _ => None,
+        },
+    }
+}
+
+/// Find the label of `row` which is the same effect as `label`, returning it
+/// and the rest of the row. If there is none and the row is open, its tail
+/// is extended with `label`, unless the tail is `avoid`: the row being
+/// unified with would then be infinite.
+fn rewrite_row<N: Name, U: Unifier<N>>(
+    ctx: &mut U,
+    row: Type<N>,
+    label: &Type<N>,
+    avoid: Option<Variable>,
+) -> Result<(Type<N>, Type<N>), UnificationError<N>> {
+    let original = row.clone();
+    let mut skipped = vec![];
+    let mut row = row;
+    loop {
+        match ctx.resolve(row) {
+            Type::Constructed(ref name, ref mut args) if args.len() == 2 && is_effects(name) => {
+                let rest = args.pop().unwrap();
+                let l = args.pop().unwrap();
+                if same_label(&l, label) {
+                    return Ok((l, extend_row(skipped, rest)));
+                }
+                skipped.push(l);
+                row = rest;
+            }
+            Type::Variable(v) => {
+                if Some(v) == avoid || label.occurs(v) {
+                    return Err(UnificationError::Occurs(v));
+                }
+                let tail = ctx.new_variable();
+                let extended = extend_row(vec![label.clone()], tail.clone());
+                ctx.extend(v, extended);
+                return Ok((label.clone(), extend_row(skipped, tail)));
+            }
+            _ => {
+                return Err(UnificationError::Failure(
+                    ctx.apply(label),
+                    ctx.apply(&original),
+                ))
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::vec;
+
+use crate::{Context, Name, Term, Type, TypeSchema};
+
+/// Lazily enumerates well-typed [`Term`]s in order of [`size`].
+///
+/// Terms are built from a library of primitives, each with a [`TypeSchema`],
+/// and are in [η-long form]: every term whose type is an arrow is an
+/// abstraction, and every primitive or variable is applied to all of its
+/// arguments. All terms of one size are found before any larger term, by
+/// instantiating each candidate's schema in a snapshot of the [`Context`] and
+/// unifying its return type with the goal. Each term is yielded as an
+/// [`Enumerated`], with the primitives' names and the goal type as refined by
+/// the term.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, Context, Enumerator, Term};
+/// let library = vec![
+///     ("zero".to_string(), ptp!(int)),
+///     ("succ".to_string(), ptp!(@arrow[tp!(int), tp!(int)])),
+///     // twice: ∀α. (α → α) → α → α
+///     ("twice".to_string(), ptp!(0; @arrow[tp!(@arrow[tp!(0), tp!(0)]), tp!(0), tp!(0)])),
+/// ];
+///
+/// let ctx = Context::default();
+/// let programs: Vec<String> = Enumerator::new(&library, &ctx, &tp!(int), 3)
+///     .map(|found| found.program)
+///     .collect();
+/// assert_eq!(
+///     programs,
+///     vec![
+///         "zero",
+///         "(succ zero)",
+///         "(succ (succ zero))",
+///         "(twice (λ $0) zero)",
+///         "(twice (λ zero) zero)",
+///     ],
+/// );
+///
+/// // the goal may be refined by the terms inhabiting it
+/// let mut ctx = Context::default();
+/// let goal = tp!(@arrow[ctx.new_variable(), tp!(int)]);
+/// let found = Enumerator::new(&library, &ctx, &goal, 1).next().unwrap();
+/// assert_eq!(found.term, Term::Abstraction(Box::new(Term::Variable(0))));
+/// assert_eq!(found.tp.to_string(), "int → int");
+/// ```
+///
+/// [`Enumerated`]: struct.Enumerated.html
+/// [`Term`]: enum.Term.html
+/// [`size`]: enum.Term.html#method.size
+/// [`TypeSchema`]: enum.TypeSchema.html
+/// [`Context`]: struct.Context.html
+/// [η-long form]: https://en.wikipedia.org/wiki/Lambda_calculus#η-reduction
+pub struct Enumerator<'a, N: Name = &'static str> {
+    library: &'a [(String, TypeSchema<N>)],
+    names: Vec<&'a str>,
+    ctx: Context<N>,
+    goal: Type<N>,
+    size: usize,
+    max_size: usize,
+    current: vec::IntoIter<(Term, Context<N>)>,
+}
+impl<'a, N: Name> Enumerator<'a, N> {
+    /// Enumerate terms of type `goal` under `ctx` with size at most
+    /// `max_size`, from a library of named primitives. Terms refer to each
+    /// primitive by its index in the library.
+    pub fn new(
+        library: &'a [(String, TypeSchema<N>)],
+        ctx: &Context<N>,
+        goal: &Type<N>,
+        max_size: usize,
+    ) -> Self {
+        Enumerator {
+            library,
+            names: library.iter().map(|(name, _)| name.as_str()).collect(),
+            ctx: ctx.clone(),
+            goal: goal.clone(),
+            size: 0,
+            max_size,
+            current: Vec::new().into_iter(),
+        }
+    }
+    /// Every term of type `goal` in `env` with exactly `size` primitives and
+    /// variables, paired with the context in which it has that type.
+    fn exactly(
+        &self,
+        ctx: &Context<N>,
+        env: &[Type<N>],
+        goal: &Type<N>,
+        size: usize,
+    ) -> Vec<(Term, Context<N>)> {
+        let goal = goal.apply(ctx);
+        if let Some((alpha, beta)) = goal.as_arrow() {
+            let mut env = env.to_vec();
+            env.insert(0, alpha.clone());
+            return self
+                .exactly(ctx, &env, beta, size)
+                .into_iter()
+                .map(|(body, ctx)| (Term::Abstraction(Box::new(body)), ctx))
+                .collect();
+        }
+        let variables = env
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (Term::Variable(i), TypeSchema::Monotype(t.clone())));
+        let primitives = self
+            .library
+            .iter()
+            .enumerate()
+            .map(|(i, (_, t))| (Term::Primitive(i), t.clone()));
+        let mut terms = vec![];
+        for (head, schema) in variables.chain(primitives) {
+            let mut ctx = ctx.clone();
+            let tp = schema.instantiate_owned(&mut ctx);
+            let args: Vec<Type<N>> = tp
+                .args()
+                .map_or_else(Vec::new, |args| args.into_iter().cloned().collect());
+            if args.len() >= size || (args.is_empty() && size != 1) {
+                continue;
+            }
+            if ctx.unify(tp.returns().unwrap_or(&tp), &goal).is_err() {
+                continue;
+            }
+            for (args, ctx) in self.arguments(&ctx, env, &args, size - 1) {
+                terms.push((Term::applied(head.clone(), args), ctx));
+            }
+        }
+        terms
+    }
+    /// Every sequence of terms of types `tps` in `env` with exactly `size`
+    /// primitives and variables in total.
+    fn arguments(
+        &self,
+        ctx: &Context<N>,
+        env: &[Type<N>],
+        tps: &[Type<N>],
+        size: usize,
+    ) -> Vec<(Vec<Term>, Context<N>)> {
+        match tps.split_first() {
+            None if size == 0 => vec![(vec![], ctx.clone())],
+            None => vec![],
+            Some((tp, rest)) => {
+                let mut results = vec![];
+                for first_size in 1..=(size - rest.len()) {
+                    for (first, ctx) in self.exactly(ctx, env, tp, first_size) {
+                        for (mut args, ctx) in self.arguments(&ctx, env, rest, size - first_size) {
+                            args.insert(0, first.clone());
+                            results.push((args, ctx));
+                        }
+                    }
+                }
+                results
+            }
+        }
+    }
+}
+impl<'a, N: Name> Iterator for Enumerator<'a, N> {
+    type Item = Enumerated<N>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((term, ctx)) = self.current.next() {
+                return Some(Enumerated {
+                    program: term.display_with_names(&self.names),
+                    term,
+                    tp: self.goal.apply(&ctx),
+                });
+            }
+            if self.size >= self.max_size {
+                return None;
+            }
+            self.size += 1;
+            self.current = self
+                .exactly(&self.ctx, &[], &self.goal, self.size)
+                .into_iter();
+        }
+    }
+}
+
+/// A term found by an [`Enumerator`].
+///
+/// [`Enumerator`]: struct.Enumerator.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Enumerated<N: Name = &'static str> {
+    /// The term, which refers to primitives by their index in the library.
+    pub term: Term,
+    /// The term shown with the names of its primitives, e.g. `(succ zero)`.
+    pub program: String,
+    /// The goal type, as refined by the term.
+    pub tp: Type<N>,
+}
@@ -0,0 +1,271 @@
+#[cfg(feature = "sampling")]
+use rand::{seq::SliceRandom, Rng};
+
+#[cfg(feature = "sampling")]
+use crate::Variable;
+use crate::{Name, Type, TypeSchema};
+
+/// Generates random, well-kinded [`Type`]s and [`TypeSchema`]s for property
+/// testing, and shrinks them while keeping them well-kinded.
+///
+/// Types are built from a table of constructors and their arities, so every
+/// constructor is always applied to the right number of arguments. Include
+/// [`Name::arrow`] with arity 2 in the table to generate function types.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "sampling")]
+/// # {
+/// # use polytype::{Name, TypeGenerator};
+/// use rand::{rngs::StdRng, SeedableRng};
+///
+/// let generator = TypeGenerator::new(vec![
+///     ("int", 0),
+///     ("list", 1),
+///     ("dict", 2),
+///     (<&'static str as Name>::arrow(), 2),
+/// ])
+/// .with_max_size(8)
+/// .with_variable_probability(0.25);
+///
+/// let mut rng = StdRng::seed_from_u64(0);
+/// for _ in 0..100 {
+///     let t = generator.generate_type(&mut rng);
+///     assert!(generator.size(&t) <= 8);
+///     assert!(generator.is_well_kinded(&t));
+///     for smaller in generator.shrink(&t) {
+///         assert!(generator.size(&smaller) <= generator.size(&t));
+///         assert!(generator.is_well_kinded(&smaller));
+///     }
+/// }
+/// # }
+/// ```
+///
+/// [`Type`]: enum.Type.html
+/// [`TypeSchema`]: enum.TypeSchema.html
+/// [`Name::arrow`]: trait.Name.html#tymethod.arrow
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeGenerator<N: Name = &'static str> {
+    constructors: Vec<(N, usize)>,
+    max_size: usize,
+    variable_probability: f64,
+    reuse_probability: f64,
+}
+impl Default for TypeGenerator<&'static str> {
+    /// Generates types from `int`, `bool`, `list` (arity 1), `pair` (arity 2),
+    /// and arrows.
+    fn default() -> Self {
+        TypeGenerator::new(vec![
+            ("int", 0),
+            ("bool", 0),
+            ("list", 1),
+            ("pair", 2),
+            (<&'static str as Name>::arrow(), 2),
+        ])
+    }
+}
+impl<N: Name> TypeGenerator<N> {
+    /// Create a generator over the given constructors and their arities.
+    ///
+    /// By default, types have at most 10 constructors and variables, each
+    /// position is a variable with probability 0.2, and each variable reuses
+    /// an earlier one with probability 0.5.
+    pub fn new(constructors: Vec<(N, usize)>) -> Self {
+        TypeGenerator {
+            constructors,
+            max_size: 10,
+            variable_probability: 0.2,
+            reuse_probability: 0.5,
+        }
+    }
+    /// Set the largest number of constructors and variables in a type.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size.max(1);
+        self
+    }
+    /// Set the probability that a position holds a variable rather than a
+    /// constructor. Positions which no constructor fits always hold variables.
+    ///
+    /// # Panics
+    ///
+    /// If `p` is not between `0` and `1`.
+    pub fn with_variable_probability(mut self, p: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "probability {} is not in [0, 1]",
+            p
+        );
+        self.variable_probability = p;
+        self
+    }
+    /// Set the probability that a variable reuses one already in the type
+    /// rather than being fresh.
+    ///
+    /// # Panics
+    ///
+    /// If `p` is not between `0` and `1`.
+    pub fn with_reuse_probability(mut self, p: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&p),
+            "probability {} is not in [0, 1]",
+            p
+        );
+        self.reuse_probability = p;
+        self
+    }
+    /// The number of constructors and variables in `t`.
+    pub fn size(&self, t: &Type<N>) -> usize {
+        t.size()
+    }
+    /// Whether every constructor in `t` is in the table and has the right
+    /// number of arguments.
+    pub fn is_well_kinded(&self, t: &Type<N>) -> bool {
+        match *t {
+            Type::Variable(_) => true,
+            Type::Constructed(ref name, ref args) => {
+                self.constructors
+                    .iter()
+                    .any(|(n, arity)| n == name && *arity == args.len())
+                    && args.iter().all(|t| self.is_well_kinded(t))
+            }
+        }
+    }
+    /// Generate a random type. Its variables are numbered from 0 in order of
+    /// appearance.
+    #[cfg(feature = "sampling")]
+    pub fn generate_type<R: Rng>(&self, rng: &mut R) -> Type<N> {
+        let size = rng.gen_range(1..=self.max_size);
+        let mut vars = 0;
+        self.generate_internal(size, &mut vars, rng)
+    }
+    #[cfg(feature = "sampling")]
+    fn generate_internal<R: Rng>(&self, size: usize, vars: &mut usize, rng: &mut R) -> Type<N> {
+        let fits: Vec<&(N, usize)> = self
+            .constructors
+            .iter()
+            .filter(|(_, arity)| *arity < size)
+            .collect();
+        if fits.is_empty() || rng.gen_bool(self.variable_probability) {
+            if *vars > 0 && rng.gen_bool(self.reuse_probability) {
+                return Type::Variable(rng.gen_range(0..*vars));
+            }
+            *vars += 1;
+            return Type::Variable(*vars - 1);
+        }
+        let (name, arity) = fits.choose(rng).unwrap();
+        let mut sizes = vec![1; *arity];
+        for _ in 0..(size - 1 - arity) {
+            if let Some(s) = sizes.choose_mut(rng) {
+                *s += 1;
+            }
+        }
+        let args = sizes
+            .into_iter()
+            .map(|size| self.generate_internal(size, vars, rng))
+            .collect();
+        Type::Constructed(name.clone(), args)
+    }
+    /// Generate a random type schema, quantifying over each of its variables
+    /// with probability 0.5.
+    #[cfg(feature = "sampling")]
+    pub fn generate_schema<R: Rng>(&self, rng: &mut R) -> TypeSchema<N> {
+        let t = self.generate_type(rng);
+        let free: Vec<Variable> = t.vars().into_iter().filter(|_| rng.gen_bool(0.5)).collect();
+        t.generalize(&free)
+    }
+    /// Simpler variants of `t`, simplest first, each replacing one subtree of
+    /// `t` with a nullary constructor, a variable, or one of its own subtrees.
+    /// Variables other than `t0` are replaced with `t0`. If `t` is
+    /// well-kinded, so is each variant.
+    pub fn shrink(&self, t: &Type<N>) -> Vec<Type<N>> {
+        match *t {
+            Type::Variable(0) => vec![],
+            Type::Variable(_) => vec![Type::Variable(0)],
+            Type::Constructed(_, ref args) if args.is_empty() => vec![],
+            Type::Constructed(ref name, ref args) => {
+                let mut smaller: Vec<Type<N>> = self
+                    .constructors
+                    .iter()
+                    .filter(|(_, arity)| *arity == 0)
+                    .map(|(name, _)| Type::Constructed(name.clone(), vec![]))
+                    .collect();
+                smaller.push(Type::Variable(t.vars().first().cloned().unwrap_or(0)));
+                smaller.extend(args.iter().cloned());
+                for (i, arg) in args.iter().enumerate() {
+                    for arg in self.shrink(arg) {
+                        let mut args = args.clone();
+                        args[i] = arg;
+                        smaller.push(Type::Constructed(name.clone(), args));
+                    }
+                }
+                smaller
+            }
+        }
+    }
+    /// Simpler variants of `t`, simplest first: its body without quantifiers,
+    /// then each variant of its body from [`shrink`], quantified over those of
+    /// `t`'s bound variables which remain.
+    ///
+    /// [`shrink`]: #method.shrink
+    pub fn shrink_schema(&self, t: &TypeSchema<N>) -> Vec<TypeSchema<N>> {
+        let bound = t.bound_vars();
+        let mut body = t;
+        while let TypeSchema::Polytype { body: ref b, .. } = *body {
+            body = b;
+        }
+        let body = match *body {
+            TypeSchema::Monotype(ref t) => t,
+            TypeSchema::Polytype { .. } => unreachable!(),
+        };
+        let mut smaller = vec![];
+        if !bound.is_empty() {
+            smaller.push(TypeSchema::Monotype(body.clone()));
+        }
+        for body in self.shrink(body) {
+            let vars = body.vars();
+            smaller.push(bound.iter().rev().filter(|v| vars.contains(v)).fold(
+                TypeSchema::Monotype(body),
+                |body, &variable| TypeSchema::Polytype {
+                    variable,
+                    body: Box::new(body),
+                },
+            ));
+        }
+        smaller
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary {
+    use quickcheck::{Arbitrary, Gen};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::TypeGenerator;
+    use crate::{Type, TypeSchema};
+
+    /// Uses the default [`TypeGenerator`].
+    ///
+    /// [`TypeGenerator`]: struct.TypeGenerator.html
+    impl Arbitrary for Type<&'static str> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let mut rng = StdRng::seed_from_u64(u64::arbitrary(g));
+            TypeGenerator::default().generate_type(&mut rng)
+        }
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            Box::new(TypeGenerator::default().shrink(self).into_iter())
+        }
+    }
+    /// Uses the default [`TypeGenerator`].
+    ///
+    /// [`TypeGenerator`]: struct.TypeGenerator.html
+    impl Arbitrary for TypeSchema<&'static str> {
+        fn arbitrary(g: &mut Gen) -> Self {
+            let mut rng = StdRng::seed_from_u64(u64::arbitrary(g));
+            TypeGenerator::default().generate_schema(&mut rng)
+        }
+        fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+            Box::new(TypeGenerator::default().shrink_schema(self).into_iter())
+        }
+    }
+}
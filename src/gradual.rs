@@ -0,0 +1,139 @@
+use crate::{Context, Name, Type};
+
+/// A cast which gradual unification would need inserted, where a value of
+/// type `from` is used at type `to` and one of them is the dynamic type `?`.
+///
+/// Unifying `t1` with `t2` records casts from parts of `t2` to the
+/// corresponding parts of `t1`, so unify the expected type with the actual
+/// one. See [`Context::set_gradual`].
+///
+/// [`Context::set_gradual`]: struct.Context.html#method.set_gradual
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cast<N: Name = &'static str> {
+    /// The type of the value.
+    pub from: Type<N>,
+    /// The type the value is used at.
+    pub to: Type<N>,
+}
+
+impl<N: Name> Type<N> {
+    /// The dynamic type `?`, for values whose type is only known at runtime.
+    ///
+    /// # Panics
+    ///
+    /// If the [`Name`] has no [`dynamic`] constructor.
+    ///
+    /// [`Name`]: trait.Name.html
+    /// [`dynamic`]: trait.Name.html#method.dynamic
+    pub fn dynamic() -> Type<N> {
+        let name = N::dynamic().expect("the name has no constructor for the dynamic type");
+        Type::Constructed(name, vec![])
+    }
+    /// Whether the type is the dynamic type `?`.
+    pub fn is_dynamic(&self) -> bool {
+        match *self {
+            Type::Constructed(ref n, ref args) => args.is_empty() && is_dynamic(n),
+            Type::Variable(_) => false,
+        }
+    }
+    /// Whether the types are consistent: equal wherever neither is the
+    /// dynamic type `?`. Unlike equality, consistency is not transitive.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Type};
+    /// let t1 = tp!(@arrow[Type::dynamic(), tp!(int)]);
+    /// let t2 = tp!(@arrow[tp!(bool), Type::dynamic()]);
+    /// assert!(t1.is_consistent(&t2));
+    /// assert_ne!(t1, t2);
+    /// assert!(!t1.is_consistent(&tp!(@arrow[tp!(bool), tp!(bool)])));
+    /// ```
+    pub fn is_consistent(&self, other: &Type<N>) -> bool {
+        let mut pending = vec![(self, other)];
+        while let Some((t1, t2)) = pending.pop() {
+            if t1.is_dynamic() || t2.is_dynamic() {
+                continue;
+            }
+            match (t1, t2) {
+                (Type::Variable(v1), Type::Variable(v2)) if v1 == v2 => (),
+                (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                    if n1 == n2 && a1.len() == a2.len() =>
+                {
+                    pending.extend(a1.iter().zip(a2))
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+pub(crate) fn is_dynamic<N: Name>(name: &N) -> bool {
+    N::dynamic().as_ref() == Some(name)
+}
+
+impl<N: Name> Context<N> {
+    /// Set whether the context is in gradual mode. In gradual mode,
+    /// [`unify`] checks that types are consistent rather than equal: the
+    /// dynamic type `?` unifies with any type without constraining it, and
+    /// a [`Cast`] is recorded for each such pair, so that an inference
+    /// engine can report where casts would be inserted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Cast, Context, Type};
+    /// let mut ctx = Context::default();
+    /// // `?` is only equal to itself
+    /// assert!(ctx.unify(&tp!(int), &Type::dynamic()).is_err());
+    ///
+    /// // applying `double: int → int` to a parsed value of type `?`
+    /// ctx.set_gradual(true);
+    /// let double = tp!(@arrow[tp!(int), tp!(int)]);
+    /// let ret = ctx.new_variable();
+    /// let apply = tp!(@arrow[Type::dynamic(), ret.clone()]);
+    /// ctx.unify(&double, &apply).expect("consistent");
+    /// assert_eq!(ret.apply(&ctx), tp!(int));
+    /// assert_eq!(
+    ///     ctx.take_casts(),
+    ///     vec![Cast { from: Type::dynamic(), to: tp!(int) }],
+    /// );
+    ///
+    /// // `?` constrains nothing
+    /// let x = ctx.new_variable();
+    /// ctx.unify(&x, &Type::dynamic()).expect("consistent");
+    /// assert_eq!(x.apply(&ctx), x);
+    /// ```
+    ///
+    /// [`unify`]: #method.unify
+    /// [`Cast`]: struct.Cast.html
+    pub fn set_gradual(&mut self, gradual: bool) {
+        self.gradual = gradual
+    }
+    /// Whether the context is in gradual mode. See [`set_gradual`].
+    ///
+    /// [`set_gradual`]: #method.set_gradual
+    pub fn is_gradual(&self) -> bool {
+        self.gradual
+    }
+    /// The casts recorded by gradual unification, in the order they were
+    /// needed. Variables in them may since have been constrained, so apply
+    /// the context before reporting them.
+    pub fn casts(&self) -> &[Cast<N>] {
+        &self.casts
+    }
+    /// Remove and return the casts recorded by gradual unification, e.g.
+    /// after unifying at each node of a term to learn where its casts go.
+    pub fn take_casts(&mut self) -> Vec<Cast<N>> {
+        std::mem::take(&mut self.casts)
+    }
+    /// Record the cast needed for `from` to be used at `to`, unless both are
+    /// `?`.
+    pub(crate) fn cast(&mut self, from: Type<N>, to: Type<N>) {
+        if to != from {
+            let (from, to) = (from.apply(self), to.apply(self));
+            self.casts.push(Cast { from, to });
+        }
+    }
+}
@@ -0,0 +1,260 @@
+#[cfg(feature = "sampling")]
+use rand::Rng;
+
+use crate::{Context, Name, Term, Type, TypeSchema};
+
+/// A probabilistic grammar over typed primitives.
+///
+/// Each production pairs a primitive's [`TypeSchema`] with a log-probability,
+/// and every variable in scope has the same log-probability. Like the
+/// [`Enumerator`], the grammar generates [`Term`]s in η-long form: terms of
+/// arrow type are abstractions, and other terms are a primitive or variable
+/// applied to all of its arguments. When choosing that primitive or variable,
+/// only those whose return type unifies with the goal type are candidates,
+/// and their probabilities are normalized over the candidates.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, Grammar, Term};
+/// let grammar = Grammar::new(
+///     0.0,
+///     vec![
+///         (0.0, ptp!(int)),                            // #0: zero
+///         (0.0, ptp!(@arrow[tp!(int), tp!(int)])),     // #1: succ
+///         (0.0, ptp!(bool)),                           // #2: true
+///         (0.0, ptp!(@arrow[tp!(int), tp!(bool)])),    // #3: is_zero
+///     ],
+/// );
+///
+/// // zero and succ are the only candidates for an int
+/// let one = Term::Application(Box::new(Term::Primitive(1)), Box::new(Term::Primitive(0)));
+/// let ll = grammar.likelihood(&one, &tp!(int));
+/// assert!((ll - 0.25f64.ln()).abs() < 1e-9);
+///
+/// // ill-typed terms are impossible
+/// assert_eq!(grammar.likelihood(&one, &tp!(bool)), f64::NEG_INFINITY);
+///
+/// // in an abstraction, the bound variable is a candidate too
+/// let id = Term::Abstraction(Box::new(Term::Variable(0)));
+/// let ll = grammar.likelihood(&id, &tp!(@arrow[tp!(int), tp!(int)]));
+/// assert!((ll - (1.0f64 / 3.0).ln()).abs() < 1e-9);
+/// ```
+///
+/// [`TypeSchema`]: enum.TypeSchema.html
+/// [`Enumerator`]: struct.Enumerator.html
+/// [`Term`]: enum.Term.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grammar<N: Name = &'static str> {
+    log_variable: f64,
+    productions: Vec<(f64, TypeSchema<N>)>,
+    max_depth: usize,
+}
+/// A way to fill a goal type: a primitive or variable, its log-probability,
+/// the types of its arguments, and the context in which it has the goal type.
+struct Candidate<N: Name> {
+    log_probability: f64,
+    head: Term,
+    args: Vec<Type<N>>,
+    ctx: Context<N>,
+}
+impl<N: Name> Grammar<N> {
+    /// Create a grammar in which each variable has log-probability
+    /// `log_variable` and the primitive [`Term::Primitive(i)`] has the
+    /// log-probability and [`TypeSchema`] given by `productions[i]`.
+    ///
+    /// [`Term::Primitive(i)`]: enum.Term.html#variant.Primitive
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    pub fn new(log_variable: f64, productions: Vec<(f64, TypeSchema<N>)>) -> Self {
+        Grammar {
+            log_variable,
+            productions,
+            max_depth: 32,
+        }
+    }
+    /// Limit how deeply [`sample`] nests applications before giving up (32 by
+    /// default), so that sampling terminates even for grammars which favor
+    /// ever larger terms.
+    ///
+    /// [`sample`]: #method.sample
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+    /// The productions of the grammar.
+    pub fn productions(&self) -> &[(f64, TypeSchema<N>)] {
+        &self.productions
+    }
+    /// The log-probability of each variable in scope.
+    pub fn log_variable(&self) -> f64 {
+        self.log_variable
+    }
+    /// The log-likelihood of generating `term` given the `goal` type, which is
+    /// `NEG_INFINITY` if `term` is not an η-long term of that type.
+    pub fn likelihood(&self, term: &Term, goal: &Type<N>) -> f64 {
+        let mut ctx = context_for(goal);
+        self.likelihood_internal(&mut ctx, &[], goal, term)
+    }
+    fn likelihood_internal(
+        &self,
+        ctx: &mut Context<N>,
+        env: &[Type<N>],
+        goal: &Type<N>,
+        term: &Term,
+    ) -> f64 {
+        let goal = goal.apply(ctx);
+        if let Some((alpha, beta)) = goal.as_arrow() {
+            return match *term {
+                Term::Abstraction(ref body) => {
+                    let mut env = env.to_vec();
+                    env.insert(0, alpha.clone());
+                    self.likelihood_internal(ctx, &env, beta, body)
+                }
+                _ => f64::NEG_INFINITY,
+            };
+        }
+        let mut args = vec![];
+        let mut head = term;
+        while let Term::Application(ref f, ref x) = *head {
+            args.push(x);
+            head = f;
+        }
+        let candidates = self.candidates(ctx, env, &goal);
+        let z = log_sum_exp(candidates.iter().map(|c| c.log_probability));
+        match candidates.into_iter().find(|c| &c.head == head) {
+            Some(ref c) if c.args.len() == args.len() => {
+                *ctx = c.ctx.clone();
+                let mut ll = c.log_probability - z;
+                for (tp, arg) in c.args.iter().zip(args.into_iter().rev()) {
+                    ll += self.likelihood_internal(ctx, env, tp, arg);
+                    if ll == f64::NEG_INFINITY {
+                        break;
+                    }
+                }
+                ll
+            }
+            _ => f64::NEG_INFINITY,
+        }
+    }
+    /// Sample a term of the `goal` type. Returns `None` when sampling reaches
+    /// a type with no candidates or exceeds the maximum depth.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "sampling")]
+    /// # {
+    /// # use polytype::{ptp, tp, Grammar};
+    /// use rand::{rngs::StdRng, SeedableRng};
+    ///
+    /// let grammar = Grammar::new(
+    ///     0.0,
+    ///     vec![
+    ///         (0.0, ptp!(int)),
+    ///         (-1.0, ptp!(@arrow[tp!(int), tp!(int), tp!(int)])),
+    ///     ],
+    /// );
+    /// let goal = tp!(@arrow[tp!(int), tp!(int)]);
+    /// let mut rng = StdRng::seed_from_u64(0);
+    /// for _ in 0..10 {
+    ///     if let Some(term) = grammar.sample(&goal, &mut rng) {
+    ///         assert!(grammar.likelihood(&term, &goal) > f64::NEG_INFINITY);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    #[cfg(feature = "sampling")]
+    pub fn sample<R: Rng>(&self, goal: &Type<N>, rng: &mut R) -> Option<Term> {
+        let mut ctx = context_for(goal);
+        self.sample_internal(&mut ctx, &[], goal, rng, 0)
+    }
+    #[cfg(feature = "sampling")]
+    fn sample_internal<R: Rng>(
+        &self,
+        ctx: &mut Context<N>,
+        env: &[Type<N>],
+        goal: &Type<N>,
+        rng: &mut R,
+        depth: usize,
+    ) -> Option<Term> {
+        let goal = goal.apply(ctx);
+        if let Some((alpha, beta)) = goal.as_arrow() {
+            let mut env = env.to_vec();
+            env.insert(0, alpha.clone());
+            return self
+                .sample_internal(ctx, &env, beta, rng, depth)
+                .map(|body| Term::Abstraction(Box::new(body)));
+        }
+        if depth > self.max_depth {
+            return None;
+        }
+        let mut candidates = self.candidates(ctx, env, &goal);
+        let z = log_sum_exp(candidates.iter().map(|c| c.log_probability));
+        let mut r = rng.gen::<f64>();
+        let i = candidates
+            .iter()
+            .position(|c| {
+                r -= (c.log_probability - z).exp();
+                r <= 0.0
+            })
+            .or_else(|| candidates.len().checked_sub(1))?;
+        let chosen = candidates.swap_remove(i);
+        *ctx = chosen.ctx;
+        let mut args = Vec::with_capacity(chosen.args.len());
+        for tp in &chosen.args {
+            args.push(self.sample_internal(ctx, env, tp, rng, depth + 1)?);
+        }
+        Some(Term::applied(chosen.head, args))
+    }
+    /// Every variable and primitive whose return type unifies with `goal`.
+    fn candidates(&self, ctx: &Context<N>, env: &[Type<N>], goal: &Type<N>) -> Vec<Candidate<N>> {
+        let variables = env.iter().enumerate().map(|(i, t)| {
+            (
+                self.log_variable,
+                Term::Variable(i),
+                TypeSchema::Monotype(t.clone()),
+            )
+        });
+        let primitives = self
+            .productions
+            .iter()
+            .enumerate()
+            .map(|(i, (lp, t))| (*lp, Term::Primitive(i), t.clone()));
+        variables
+            .chain(primitives)
+            .filter_map(|(log_probability, head, schema)| {
+                let mut ctx = ctx.clone();
+                let tp = schema.instantiate_owned(&mut ctx);
+                ctx.unify(tp.returns().unwrap_or(&tp), goal).ok()?;
+                let args = tp
+                    .args()
+                    .map_or_else(Vec::new, |args| args.into_iter().cloned().collect());
+                Some(Candidate {
+                    log_probability,
+                    head,
+                    args,
+                    ctx,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A context in which fresh variables do not clash with those in `goal`.
+fn context_for<N: Name>(goal: &Type<N>) -> Context<N> {
+    let mut ctx = Context::default();
+    for _ in 0..goal.vars().last().map_or(0, |v| v + 1) {
+        ctx.new_variable();
+    }
+    ctx
+}
+
+fn log_sum_exp<I: Iterator<Item = f64>>(xs: I) -> f64 {
+    let xs: Vec<f64> = xs.collect();
+    let max = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        max
+    } else {
+        max + xs.iter().map(|x| (x - max).exp()).sum::<f64>().ln()
+    }
+}
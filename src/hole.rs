@@ -0,0 +1,207 @@
+use std::cmp::Reverse;
+use std::rc::Rc;
+
+use crate::{Context, Name, Type, TypeSchema};
+
+/// Named bindings, as in scope at a hole, shared between the holes there.
+type Scope<N> = Rc<[(String, TypeSchema<N>)]>;
+
+/// Records the typed holes (e.g. `?hole`) met while inferring the type of an
+/// expression, to report what each hole expects once inference is done.
+///
+/// An inference engine calls [`hole`] for each hole, with the bindings in
+/// scope there, and uses the returned type as the hole's. Scopes are shared
+/// with the engine rather than copied for each hole. After inference,
+/// [`report`] gives, for each hole, its expected type under the final
+/// [`Context`], and the bindings which could fill it.
+///
+/// # Examples
+///
+/// ```
+/// use polytype::{ptp, tp, Context, Infer, Type, TypedHoles, TypeSchema, UnificationError};
+/// use std::cell::RefCell;
+/// use std::rc::Rc;
+///
+/// enum Expr {
+///     Var(&'static str),
+///     App(Box<Expr>, Box<Expr>),
+///     Hole(&'static str),
+/// }
+/// struct Env {
+///     ctx: RefCell<Context>,
+///     scope: Rc<[(String, TypeSchema)]>,
+///     holes: RefCell<TypedHoles>,
+/// }
+/// impl Infer<Env> for Expr {
+///     fn infer(&self, env: &Env) -> Result<Type, UnificationError> {
+///         match self {
+///             Expr::Var(x) => {
+///                 let (_, schema) = env.scope.iter().find(|(y, _)| y == x).unwrap();
+///                 Ok(schema.instantiate(&mut env.ctx.borrow_mut()))
+///             }
+///             Expr::App(f, x) => {
+///                 let ft = f.infer(env)?;
+///                 let xt = x.infer(env)?;
+///                 let mut ctx = env.ctx.borrow_mut();
+///                 let ret = ctx.new_variable();
+///                 ctx.unify(&ft, &Type::arrow(xt, ret.clone()))?;
+///                 Ok(ret.apply(&ctx))
+///             }
+///             Expr::Hole(name) => {
+///                 let mut ctx = env.ctx.borrow_mut();
+///                 Ok(env.holes.borrow_mut().hole(name, &mut ctx, &env.scope))
+///             }
+///         }
+///     }
+/// }
+///
+/// let env = Env {
+///     ctx: RefCell::new(Context::default()),
+///     scope: vec![
+///         ("length".into(), ptp!(0; @arrow[tp!(list(tp!(0))), tp!(int)])),
+///         ("sum".into(), ptp!(@arrow[tp!(list(tp!(int))), tp!(int)])),
+///         ("negate".into(), ptp!(@arrow[tp!(int), tp!(int)])),
+///         ("xs".into(), ptp!(list(tp!(int)))),
+///     ]
+///     .into(),
+///     holes: RefCell::new(TypedHoles::new()),
+/// };
+/// // negate (?f xs)
+/// let expr = Expr::App(
+///     Box::new(Expr::Var("negate")),
+///     Box::new(Expr::App(Box::new(Expr::Hole("f")), Box::new(Expr::Var("xs")))),
+/// );
+/// assert_eq!(expr.infer(&env), Ok(tp!(int)));
+///
+/// let reports = env.holes.borrow().report(&env.ctx.borrow());
+/// assert_eq!(reports[0].name, "f");
+/// assert_eq!(reports[0].expected.to_string(), "list(int) → int");
+/// let candidates: Vec<_> = reports[0].candidates.iter().map(|c| c.name.as_str()).collect();
+/// assert_eq!(candidates, vec!["sum", "length"]);
+/// ```
+///
+/// [`hole`]: #method.hole
+/// [`report`]: #method.report
+/// [`Context`]: struct.Context.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypedHoles<N: Name = &'static str> {
+    holes: Vec<(String, Type<N>, Scope<N>)>,
+}
+impl<N: Name> Default for TypedHoles<N> {
+    fn default() -> Self {
+        TypedHoles { holes: vec![] }
+    }
+}
+impl<N: Name> TypedHoles<N> {
+    /// Create a recorder with no holes.
+    pub fn new() -> Self {
+        TypedHoles::default()
+    }
+    /// Record the hole `name`, with the bindings in `scope` there, returning
+    /// a fresh variable of `ctx` to use as its type.
+    pub fn hole(&mut self, name: &str, ctx: &mut Context<N>, scope: &Scope<N>) -> Type<N> {
+        let tp = ctx.new_variable();
+        self.holes
+            .push((name.to_string(), tp.clone(), Rc::clone(scope)));
+        tp
+    }
+    /// The number of holes recorded.
+    pub fn len(&self) -> usize {
+        self.holes.len()
+    }
+    /// Whether no holes have been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.holes.is_empty()
+    }
+    /// Report each hole under `ctx`, in the order they were recorded. See
+    /// [`HoleReport`].
+    ///
+    /// [`HoleReport`]: struct.HoleReport.html
+    pub fn report(&self, ctx: &Context<N>) -> Vec<HoleReport<N>> {
+        self.holes
+            .iter()
+            .map(|(name, tp, scope)| {
+                let expected = tp.apply(ctx);
+                let candidates = candidates(ctx, &expected, scope);
+                HoleReport {
+                    name: name.clone(),
+                    expected,
+                    candidates,
+                }
+            })
+            .collect()
+    }
+}
+
+/// What a typed hole expects. See [`TypedHoles`].
+///
+/// [`TypedHoles`]: struct.TypedHoles.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoleReport<N: Name = &'static str> {
+    /// The name of the hole.
+    pub name: String,
+    /// The type of the hole, with the context applied.
+    pub expected: Type<N>,
+    /// The bindings in scope whose schemas can be instantiated to unify with
+    /// the expected type, most specific first: a binding is more specific
+    /// the more constructors its schema has. Bindings equally specific stay
+    /// in the order of the scope.
+    pub candidates: Vec<HoleCandidate<N>>,
+}
+
+/// A binding which could fill a typed hole. See [`HoleReport`].
+///
+/// [`HoleReport`]: struct.HoleReport.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoleCandidate<N: Name = &'static str> {
+    /// The name of the binding.
+    pub name: String,
+    /// The schema of the binding, with the context applied.
+    pub schema: TypeSchema<N>,
+    /// The type the binding would have in the hole.
+    pub instance: Type<N>,
+}
+
+/// The bindings of `scope` which can fill a hole of type `expected`, most
+/// specific first.
+fn candidates<N: Name>(
+    ctx: &Context<N>,
+    expected: &Type<N>,
+    scope: &[(String, TypeSchema<N>)],
+) -> Vec<HoleCandidate<N>> {
+    // each binding is tried in the same scratch context, rolled back after.
+    let mut scratch = ctx.clone();
+    let (n, next) = (scratch.len(), scratch.next);
+    let mut candidates: Vec<_> = scope
+        .iter()
+        .filter_map(|(name, schema)| {
+            let schema = schema.apply(ctx);
+            let tp = schema.instantiate(&mut scratch);
+            let instance = scratch.unify(&tp, expected).map(|()| tp.apply(&scratch));
+            scratch.rollback(n);
+            scratch.next = next;
+            let instance = instance.ok()?;
+            let candidate = HoleCandidate {
+                name: name.clone(),
+                schema,
+                instance,
+            };
+            Some((constructors(&tp), candidate))
+        })
+        .collect();
+    candidates.sort_by_key(|&(specificity, _)| Reverse(specificity));
+    candidates.into_iter().map(|(_, c)| c).collect()
+}
+
+/// The number of constructors in `t`.
+fn constructors<N: Name>(t: &Type<N>) -> usize {
+    let mut n = 0;
+    let mut stack = vec![t];
+    while let Some(t) = stack.pop() {
+        if let Type::Constructed(_, ref args) = *t {
+            n += 1;
+            stack.extend(args);
+        }
+    }
+    n
+}
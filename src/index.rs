@@ -0,0 +1,248 @@
+use crate::nat::is_nat;
+use crate::units::is_dimension;
+use crate::{Name, Type, TypeSchema};
+
+/// An index of values keyed by [`Type`]s, for quickly finding the keys which
+/// might unify with, be instances of, or be generalizations of a query.
+///
+/// The index is a discrimination tree: each key is flattened into the
+/// sequence of its constructors, in preorder, with every variable replaced by
+/// a wildcard. Queries walk the tree, skipping whole subtrees where either
+/// side has a wildcard. Because variables are not told apart, the index may
+/// return candidates which share a variable between positions that cannot
+/// agree (e.g. `t0 → t0` for `int → bool`), and it ignores the effects of
+/// arrows and the insides of dimensions and naturals, so confirm each
+/// candidate with [`Context::unify`]. It never misses a key that matches,
+/// except under [gradual] unification: `?` is matched as a constructor, so
+/// keys which are only consistent with the query through `?` are missed.
+///
+/// Values are returned in the order they were inserted.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, TypeIndex};
+/// let mut index = TypeIndex::new();
+/// index.insert_schema(&ptp!(@arrow[tp!(int), tp!(int)]), "succ");
+/// index.insert_schema(&ptp!(0; @arrow[tp!(0), tp!(0)]), "id");
+/// index.insert_schema(&ptp!(@arrow[tp!(bool), tp!(bool)]), "not");
+/// index.insert_schema(&ptp!(0; @arrow[tp!(list(tp!(0))), tp!(int)]), "length");
+///
+/// let goal = tp!(@arrow[tp!(int), tp!(0)]);
+/// assert_eq!(index.unifiable_with(&goal), vec![&"succ", &"id"]);
+/// assert_eq!(index.instances_of(&goal), vec![&"succ"]);
+///
+/// let goal = tp!(@arrow[tp!(bool), tp!(bool)]);
+/// assert_eq!(index.generalizations_of(&goal), vec![&"id", &"not"]);
+/// ```
+///
+/// [`Type`]: enum.Type.html
+/// [`Context::unify`]: struct.Context.html#method.unify
+/// [gradual]: struct.Context.html#method.set_gradual
+#[derive(Debug, Clone)]
+pub struct TypeIndex<T, N: Name = &'static str> {
+    root: Node<T, N>,
+    len: usize,
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Key<N: Name> {
+    /// A constructor and its number of arguments.
+    Constructed(N, usize),
+    /// Any variable.
+    Variable,
+}
+impl<N: Name> Key<N> {
+    fn arity(&self) -> usize {
+        match *self {
+            Key::Constructed(_, arity) => arity,
+            Key::Variable => 0,
+        }
+    }
+}
+#[derive(Debug, Clone)]
+struct Node<T, N: Name> {
+    /// The values whose keys end here, with the order of their insertion.
+    values: Vec<(usize, T)>,
+    children: Vec<(Key<N>, Node<T, N>)>,
+}
+impl<T, N: Name> Default for Node<T, N> {
+    fn default() -> Self {
+        Node {
+            values: vec![],
+            children: vec![],
+        }
+    }
+}
+/// How query variables and stored variables may be matched.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Either may match anything.
+    Unify,
+    /// Query variables match anything, stored variables only variables.
+    Instance,
+    /// Stored variables match anything, query variables only variables.
+    Generalization,
+}
+impl<T, N: Name> Default for TypeIndex<T, N> {
+    fn default() -> Self {
+        TypeIndex {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+impl<T, N: Name> TypeIndex<T, N> {
+    /// Create an empty index.
+    pub fn new() -> Self {
+        TypeIndex::default()
+    }
+    /// The number of values in the index.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    /// Whether the index has no values.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Add `value` to the index under the key `t`.
+    pub fn insert(&mut self, t: &Type<N>, value: T) {
+        let mut node = &mut self.root;
+        for key in flatten(t) {
+            let i = match node.children.iter().position(|(k, _)| *k == key) {
+                Some(i) => i,
+                None => {
+                    node.children.push((key, Node::default()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[i].1;
+        }
+        node.values.push((self.len, value));
+        self.len += 1;
+    }
+    /// Add `value` to the index under the body of `schema`. Bound and free
+    /// variables are alike wildcards in the index.
+    pub fn insert_schema(&mut self, schema: &TypeSchema<N>, value: T) {
+        let mut body = schema;
+        while let TypeSchema::Polytype { body: ref b, .. } = *body {
+            body = b;
+        }
+        match *body {
+            TypeSchema::Monotype(ref t) => self.insert(t, value),
+            TypeSchema::Polytype { .. } => unreachable!(),
+        }
+    }
+    /// The values whose keys might unify with `t`.
+    pub fn unifiable_with(&self, t: &Type<N>) -> Vec<&T> {
+        self.retrieve(t, Mode::Unify)
+    }
+    /// The values whose keys might be instances of `t`, i.e. `t` with its
+    /// variables replaced.
+    pub fn instances_of(&self, t: &Type<N>) -> Vec<&T> {
+        self.retrieve(t, Mode::Instance)
+    }
+    /// The values whose keys might be generalizations of `t`, i.e. of which
+    /// `t` might be an instance.
+    pub fn generalizations_of(&self, t: &Type<N>) -> Vec<&T> {
+        self.retrieve(t, Mode::Generalization)
+    }
+    fn retrieve(&self, t: &Type<N>, mode: Mode) -> Vec<&T> {
+        let mut found = vec![];
+        retrieve_internal(&self.root, &mut vec![t], mode, &mut found);
+        found.sort_by_key(|&(i, _)| i);
+        found.into_iter().map(|(_, v)| v).collect()
+    }
+}
+
+/// The keys of `t` in preorder.
+fn flatten<N: Name>(t: &Type<N>) -> Vec<Key<N>> {
+    let mut keys = vec![];
+    let mut stack = vec![t];
+    while let Some(t) = stack.pop() {
+        match *t {
+            Type::Variable(_) => keys.push(Key::Variable),
+            Type::Constructed(ref name, ref args) => {
+                let args = indexed_args(name, args);
+                keys.push(Key::Constructed(name.clone(), args.len()));
+                stack.extend(args.iter().rev());
+            }
+        }
+    }
+    keys
+}
+
+/// Collect the values under `node` which match the query types on `stack`,
+/// the next of which is on top.
+fn retrieve_internal<'a, T, N: Name>(
+    node: &'a Node<T, N>,
+    stack: &mut Vec<&Type<N>>,
+    mode: Mode,
+    found: &mut Vec<(usize, &'a T)>,
+) {
+    let t = match stack.pop() {
+        Some(t) => t,
+        None => {
+            found.extend(node.values.iter().map(|(i, v)| (*i, v)));
+            return;
+        }
+    };
+    match *t {
+        Type::Variable(_) if mode != Mode::Generalization => {
+            for node in skip(node, 1) {
+                retrieve_internal(node, stack, mode, found);
+            }
+        }
+        Type::Variable(_) => {
+            if let Some(node) = child(node, &Key::Variable) {
+                retrieve_internal(node, stack, mode, found);
+            }
+        }
+        Type::Constructed(ref name, ref args) => {
+            let args = indexed_args(name, args);
+            if mode != Mode::Instance {
+                if let Some(node) = child(node, &Key::Variable) {
+                    retrieve_internal(node, stack, mode, found);
+                }
+            }
+            if let Some(node) = child(node, &Key::Constructed(name.clone(), args.len())) {
+                let depth = stack.len();
+                stack.extend(args.iter().rev());
+                retrieve_internal(node, stack, mode, found);
+                stack.truncate(depth);
+            }
+        }
+    }
+    stack.push(t);
+}
+
+/// The arguments of a constructed type which are indexed: the effects of an
+/// arrow are left out, so that arrows match whatever their effects, as are
+/// the factors of a dimension and the terms of a natural, which match modulo
+/// arithmetic.
+fn indexed_args<'a, N: Name>(name: &N, args: &'a [Type<N>]) -> &'a [Type<N>] {
+    if is_dimension(name) || is_nat(name) {
+        &[]
+    } else if name.is_arrow() && args.len() > 2 {
+        &args[..2]
+    } else {
+        args
+    }
+}
+
+fn child<'a, T, N: Name>(node: &'a Node<T, N>, key: &Key<N>) -> Option<&'a Node<T, N>> {
+    node.children
+        .iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, node)| node)
+}
+
+/// The nodes reached from `node` by skipping `n` whole types.
+fn skip<T, N: Name>(node: &Node<T, N>, n: usize) -> Vec<&Node<T, N>> {
+    if n == 0 {
+        return vec![node];
+    }
+    node.children
+        .iter()
+        .flat_map(|(key, node)| skip(node, n - 1 + key.arity()))
+        .collect()
+}
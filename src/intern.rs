@@ -0,0 +1,225 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+
+use crate::{Context, Name, Type, TypeSchema, Variable};
+
+/// A hash-consed [`Type`], created by a [`TypeInterner`].
+///
+/// Structurally equal types from the same interner are the same node, so
+/// equality is pointer comparison and hashing uses a hash cached in the node.
+/// Cloning is cheap, and substitution shares every subtree it leaves
+/// unchanged. Comparing types from different interners is meaningless.
+///
+/// [`Type`]: enum.Type.html
+/// [`TypeInterner`]: struct.TypeInterner.html
+#[derive(Clone)]
+pub struct SharedType<N: Name = &'static str>(Rc<Node<N>>);
+struct Node<N: Name> {
+    kind: SharedTypeKind<N>,
+    hash: u64,
+    ground: bool,
+}
+/// The shape of a [`SharedType`]: a constructor applied to shared arguments,
+/// or a variable.
+///
+/// [`SharedType`]: struct.SharedType.html
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub enum SharedTypeKind<N: Name = &'static str> {
+    /// Primitive or composite types (e.g. `int`, `List(α)`, `α → β`)
+    Constructed(N, Vec<SharedType<N>>),
+    /// Type variables (e.g. `α`, `β`).
+    Variable(Variable),
+}
+impl<N: Name> SharedType<N> {
+    /// The constructor and arguments, or variable, at the root of the type.
+    pub fn kind(&self) -> &SharedTypeKind<N> {
+        &self.0.kind
+    }
+    /// Whether the type has no variables.
+    pub fn is_ground(&self) -> bool {
+        self.0.ground
+    }
+    /// Convert back to a [`Type`].
+    ///
+    /// [`Type`]: enum.Type.html
+    pub fn to_type(&self) -> Type<N> {
+        match *self.kind() {
+            SharedTypeKind::Constructed(ref name, ref args) => {
+                Type::Constructed(name.clone(), args.iter().map(|t| t.to_type()).collect())
+            }
+            SharedTypeKind::Variable(v) => Type::Variable(v),
+        }
+    }
+}
+impl<N: Name> PartialEq for SharedType<N> {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
+impl<N: Name> Eq for SharedType<N> {}
+impl<N: Name> Hash for SharedType<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.0.hash)
+    }
+}
+impl<N: Name> fmt::Debug for SharedType<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SharedType({})", self.to_type())
+    }
+}
+impl<N: Name> fmt::Display for SharedType<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_type())
+    }
+}
+impl<'a, N: Name> From<&'a SharedType<N>> for Type<N> {
+    fn from(t: &'a SharedType<N>) -> Type<N> {
+        t.to_type()
+    }
+}
+
+/// Creates [`SharedType`]s, ensuring each structurally distinct type is
+/// created only once.
+///
+/// Every type it creates is kept alive until the interner is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{tp, Context, TypeInterner};
+/// let mut interner = TypeInterner::new();
+/// let t = interner.intern(&tp!(@arrow[tp!(list(tp!(int))), tp!(0)]));
+/// assert_eq!(t, interner.intern(&tp!(@arrow[tp!(list(tp!(int))), tp!(0)])));
+/// assert!(!t.is_ground());
+///
+/// let mut ctx = Context::default();
+/// ctx.extend(0, tp!(bool));
+/// let applied = interner.apply(&t, &ctx);
+/// assert!(applied.is_ground());
+/// assert_eq!(applied.to_type(), tp!(@arrow[tp!(list(tp!(int))), tp!(bool)]));
+///
+/// // only bool and the new arrow were created: list(int) is shared
+/// assert_eq!(interner.len(), 6);
+/// ```
+///
+/// [`SharedType`]: struct.SharedType.html
+pub struct TypeInterner<N: Name + Hash = &'static str> {
+    table: HashMap<SharedTypeKind<N>, SharedType<N>>,
+}
+impl<N: Name + Hash> Default for TypeInterner<N> {
+    fn default() -> Self {
+        TypeInterner {
+            table: HashMap::new(),
+        }
+    }
+}
+impl<N: Name + Hash> TypeInterner<N> {
+    /// Create an empty interner.
+    pub fn new() -> Self {
+        TypeInterner::default()
+    }
+    /// The number of distinct types created so far.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+    /// Whether no types have been created.
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+    /// The shared type variable `v`.
+    pub fn variable(&mut self, v: Variable) -> SharedType<N> {
+        self.make(SharedTypeKind::Variable(v))
+    }
+    /// The shared type `name(args…)`.
+    pub fn constructed(&mut self, name: N, args: Vec<SharedType<N>>) -> SharedType<N> {
+        self.make(SharedTypeKind::Constructed(name, args))
+    }
+    /// The shared function type `alpha → beta`.
+    pub fn arrow(&mut self, alpha: SharedType<N>, beta: SharedType<N>) -> SharedType<N> {
+        self.constructed(N::arrow(), vec![alpha, beta])
+    }
+    fn make(&mut self, kind: SharedTypeKind<N>) -> SharedType<N> {
+        if let Some(t) = self.table.get(&kind) {
+            return t.clone();
+        }
+        let mut hasher = DefaultHasher::new();
+        kind.hash(&mut hasher);
+        let ground = match kind {
+            SharedTypeKind::Constructed(_, ref args) => args.iter().all(SharedType::is_ground),
+            SharedTypeKind::Variable(_) => false,
+        };
+        let t = SharedType(Rc::new(Node {
+            kind: kind.clone(),
+            hash: hasher.finish(),
+            ground,
+        }));
+        self.table.insert(kind, t.clone());
+        t
+    }
+    /// Convert a [`Type`] into a shared type.
+    ///
+    /// [`Type`]: enum.Type.html
+    pub fn intern(&mut self, t: &Type<N>) -> SharedType<N> {
+        match *t {
+            Type::Constructed(ref name, ref args) => {
+                let args = args.iter().map(|t| self.intern(t)).collect();
+                self.constructed(name.clone(), args)
+            }
+            Type::Variable(v) => self.variable(v),
+        }
+    }
+    /// Substitute for the variables of `t`, sharing every subtree with no
+    /// substituted variables.
+    pub fn substitute(
+        &mut self,
+        t: &SharedType<N>,
+        substitution: &HashMap<Variable, SharedType<N>>,
+    ) -> SharedType<N> {
+        self.map_vars(t, &mut |_, v| substitution.get(&v).cloned())
+    }
+    /// Like [`Type::apply`], but sharing every subtree left unchanged by the
+    /// [`Context`].
+    ///
+    /// [`Type::apply`]: enum.Type.html#method.apply
+    /// [`Context`]: struct.Context.html
+    pub fn apply(&mut self, t: &SharedType<N>, ctx: &Context<N>) -> SharedType<N> {
+        self.map_vars(t, &mut |interner, v| match Type::Variable(v).apply(ctx) {
+            Type::Variable(w) if w == v => None,
+            t => Some(interner.intern(&t)),
+        })
+    }
+    /// Instantiate `schema` in `ctx` as a shared type.
+    pub fn instantiate(&mut self, schema: &TypeSchema<N>, ctx: &mut Context<N>) -> SharedType<N> {
+        let t = schema.instantiate(ctx);
+        self.intern(&t)
+    }
+    fn map_vars<F>(&mut self, t: &SharedType<N>, f: &mut F) -> SharedType<N>
+    where
+        F: FnMut(&mut Self, Variable) -> Option<SharedType<N>>,
+    {
+        if t.is_ground() {
+            return t.clone();
+        }
+        match *t.kind() {
+            SharedTypeKind::Variable(v) => f(self, v).unwrap_or_else(|| t.clone()),
+            SharedTypeKind::Constructed(ref name, ref args) => {
+                let new_args: Vec<_> = args.iter().map(|arg| self.map_vars(arg, f)).collect();
+                if new_args == *args {
+                    t.clone()
+                } else {
+                    self.constructed(name.clone(), new_args)
+                }
+            }
+        }
+    }
+}
+impl<N: Name + Hash> fmt::Debug for TypeInterner<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeInterner")
+            .field("len", &self.table.len())
+            .finish()
+    }
+}
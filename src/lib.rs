@@ -100,14 +100,57 @@
 //! [Hindley-Milner polymorphic typing system]: https://en.wikipedia.org/wiki/Hindley–Milner_type_system
 
 mod context;
+mod data;
+mod effect;
+mod enumerate;
+mod generate;
+mod gradual;
+mod grammar;
+mod hole;
+mod index;
+mod intern;
 mod macros;
 #[cfg(feature = "parser")]
+mod module;
+mod nat;
+#[cfg(feature = "parser")]
 mod parser;
+mod partial;
+#[cfg(feature = "persistent")]
+mod persistent;
+mod pretty;
+mod substitution;
+mod subtype;
+mod term;
+mod theory;
 mod types;
+mod units;
+mod visit;
 
-pub use context::{Context, ContextChange, UnificationError};
+pub use context::{Context, ContextChange, SolvedContext, UnificationError};
+pub use data::{DataConstructor, DataDecl};
+pub use enumerate::{Enumerated, Enumerator};
+pub use generate::TypeGenerator;
+pub use gradual::Cast;
+pub use grammar::Grammar;
+pub use hole::{HoleCandidate, HoleReport, TypedHoles};
+pub use index::TypeIndex;
+pub use intern::{SharedType, SharedTypeKind, TypeInterner};
+#[cfg(feature = "parser")]
+pub use module::{Module, ModuleError, ModuleErrorKind};
 pub use parser::ParseError;
+pub use partial::PartialSignature;
+#[cfg(feature = "persistent")]
+pub use persistent::PersistentContext;
+pub use pretty::{PrettyConfig, VariableNaming};
+pub use substitution::Substitution;
+pub use subtype::{Bounds, SubtypeLattice, Variance};
+pub use term::Term;
 pub use types::{Type, TypeSchema, Variable};
+pub use visit::{TypeFolder, TypeVisitor};
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
 
 /// Types require a `Name` for comparison.
 ///
@@ -160,6 +203,60 @@ pub trait Name: Clone + Eq {
     fn is_arrow(&self) -> bool {
         *self == Self::arrow()
     }
+    /// A name for tuples, if any. Tuples of two or more types are then written
+    /// `(α, β, …)` instead of `tuple(α, β, …)` when displaying and parsing.
+    fn tuple() -> Option<Self> {
+        None
+    }
+    /// A name for lists, if any. Lists are then written `[α]` instead of
+    /// `list(α)` when displaying and parsing.
+    fn list() -> Option<Self> {
+        None
+    }
+    /// A name for effect rows, if any. Arrows may then carry the side effects
+    /// of a function, written `α →{io | ε} β`. See [`Type::effect_arrow`].
+    ///
+    /// [`Type::effect_arrow`]: enum.Type.html#method.effect_arrow
+    fn effects() -> Option<Self> {
+        None
+    }
+    /// A name for dimensions, if any. Types may then be parameterized by units
+    /// of measure, written `float(m·s^-1)`. See [`Type::dimension`].
+    ///
+    /// [`Type::dimension`]: enum.Type.html#method.dimension
+    fn dimension() -> Option<Self> {
+        None
+    }
+    /// A name for type-level naturals, if any. Types may then be
+    /// parameterized by sizes, written `vec(n+1, α)`. See [`Type::nat_sum`].
+    ///
+    /// [`Type::nat_sum`]: enum.Type.html#method.nat_sum
+    fn nat() -> Option<Self> {
+        None
+    }
+    /// A name for the number `n`, if any, which must round-trip with
+    /// [`as_number`]. Naturals and dimensions keep their constants,
+    /// coefficients, and exponents in such names, so it is needed for either.
+    ///
+    /// [`as_number`]: #method.as_number
+    fn number(_n: usize) -> Option<Self> {
+        None
+    }
+    /// The number this is a name for, if it was given by [`number`].
+    ///
+    /// [`number`]: #method.number
+    fn as_number(&self) -> Option<usize> {
+        None
+    }
+    /// A name for the dynamic type, if any. It is then written `?`, and a
+    /// [`Context`] in gradual mode treats it as consistent with every type.
+    /// See [`Context::set_gradual`].
+    ///
+    /// [`Context`]: struct.Context.html
+    /// [`Context::set_gradual`]: struct.Context.html#method.set_gradual
+    fn dynamic() -> Option<Self> {
+        None
+    }
 }
 impl Name for &'static str {
     /// The rightwards arrow in unicode: `→`.
@@ -181,6 +278,45 @@ impl Name for &'static str {
     fn is_arrow(&self) -> bool {
         *self == "→"
     }
+    /// `!`, which cannot be parsed as an ordinary constructor.
+    #[inline(always)]
+    fn effects() -> Option<&'static str> {
+        Some("!")
+    }
+    /// `·`, which cannot be parsed as an ordinary constructor.
+    #[inline(always)]
+    fn dimension() -> Option<&'static str> {
+        Some("·")
+    }
+    /// `+`, which cannot be parsed as an ordinary constructor.
+    #[inline(always)]
+    fn nat() -> Option<&'static str> {
+        Some("+")
+    }
+    /// The number in decimal. **LEAKY** because it gives the string a static
+    /// lifetime, though each number is only leaked once.
+    fn number(n: usize) -> Option<&'static str> {
+        static NUMBERS: Mutex<BTreeMap<usize, &'static str>> = Mutex::new(BTreeMap::new());
+        let mut numbers = NUMBERS.lock().unwrap_or_else(|e| e.into_inner());
+        Some(
+            numbers
+                .entry(n)
+                .or_insert_with(|| Box::leak(n.to_string().into_boxed_str())),
+        )
+    }
+    /// The number, if the string is one in decimal.
+    fn as_number(&self) -> Option<usize> {
+        if self.bytes().all(|b| b.is_ascii_digit()) {
+            self.parse().ok()
+        } else {
+            None
+        }
+    }
+    /// `?`, which cannot be parsed as an ordinary constructor.
+    #[inline(always)]
+    fn dynamic() -> Option<&'static str> {
+        Some("?")
+    }
 }
 
 /// An interface for things that are typable.
@@ -0,0 +1,352 @@
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::{error, fmt};
+
+use crate::{DataDecl, Name, ParseError, Type, TypeSchema};
+
+/// The ways in which a line of a [`Module`] can be invalid.
+///
+/// [`Module`]: struct.Module.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleErrorKind {
+    /// The line is not a comment, signature, alias, constructor, or data type
+    /// declaration.
+    Syntax,
+    /// The type on the line could not be parsed.
+    Type,
+    /// The name was already declared by an earlier line.
+    Duplicate(String),
+    /// A constructor or alias was given the wrong number of arguments.
+    Arity {
+        /// The constructor or alias.
+        name: String,
+        /// The number of arguments it was declared with.
+        expected: usize,
+        /// The number of arguments it was given.
+        found: usize,
+    },
+}
+
+/// An error on a particular line of a [`Module`].
+///
+/// [`Module`]: struct.Module.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleError {
+    /// The line on which the error occurred, counting from 1.
+    pub line: usize,
+    /// What went wrong.
+    pub kind: ModuleErrorKind,
+}
+impl fmt::Display for ModuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match self.kind {
+            ModuleErrorKind::Syntax => write!(f, "expected a declaration"),
+            ModuleErrorKind::Type => write!(f, "invalid type"),
+            ModuleErrorKind::Duplicate(ref name) => write!(f, "{} is already declared", name),
+            ModuleErrorKind::Arity {
+                ref name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} expects {} argument(s) but was given {}",
+                name, expected, found
+            ),
+        }
+    }
+}
+impl error::Error for ModuleError {}
+impl From<ParseError> for ModuleErrorKind {
+    fn from(_: ParseError) -> Self {
+        ModuleErrorKind::Type
+    }
+}
+
+/// A collection of type signatures, parsed from a file of declarations.
+///
+/// Each line of the file is blank or holds one declaration, and anything
+/// following a `#` is a comment. The declarations are:
+///
+/// - signatures, `name : schema`, which give the [`TypeSchema`] of `name`.
+///   The schema is parsed by [`TypeSchema::parse_named`].
+/// - aliases, `type name(a, b, …) = type` or `type name = type`, which are
+///   expanded in every later declaration.
+/// - constructors, `constructor name(a, b, …)` or `constructor name`, which
+///   declare the number of arguments `name` takes. Later declarations using
+///   `name` with a different number of arguments are errors. Undeclared
+///   constructors may take any number of arguments.
+/// - data types, `data name(a, b, …) = c1(…) | c2(…) | …`, which are parsed
+///   by [`DataDecl::parse`]. They declare `name` as a constructor, and a
+///   signature for each of their constructors.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, Module};
+/// let module: Module = Module::parse("
+///     ## lists
+///     constructor list(a)
+///     type predicate(a) = a → bool
+///
+///     map : ∀a b. (a → b) → list(a) → list(b)
+///     filter : ∀a. predicate(a) → list(a) → list(a)  # keep what satisfies the predicate
+/// ").expect("valid module");
+///
+/// assert_eq!(module.arities()["list"], 1);
+/// assert_eq!(
+///     module.signatures()["filter"],
+///     ptp!(0; @arrow[tp!(@arrow[tp!(0), tp!(bool)]), tp!(list(tp!(0))), tp!(list(tp!(0)))]),
+/// );
+/// assert_eq!(module.signatures()["map"].to_string(), "∀t0. ∀t1. (t0 → t1) → list(t0) → list(t1)");
+/// ```
+///
+/// Data types also declare their constructors:
+///
+/// ```
+/// # use polytype::Module;
+/// let module: Module = Module::parse("
+///     data maybe(a) = none | some(a)
+///     from_maybe : ∀a. a → maybe(a) → a
+/// ").expect("valid module");
+///
+/// assert_eq!(module.arities()["maybe"], 1);
+/// assert_eq!(module.signatures()["some"].to_string(), "∀t0. t0 → maybe(t0)");
+/// assert_eq!(
+///     module.data_types()["maybe"].fold().to_string(),
+///     "∀t0. ∀t1. maybe(t0) → t1 → (t0 → t1) → t1",
+/// );
+/// ```
+///
+/// Every invalid line is reported:
+///
+/// ```
+/// # use polytype::{Module, ModuleError, ModuleErrorKind};
+/// let errors = Module::<&'static str>::parse("
+///     constructor list(a)
+///     head : ∀a. list(a, a) → a
+///     tail ∀a. list(a) → list(a)
+///     head : int
+/// ").expect_err("invalid module");
+///
+/// assert_eq!(errors, vec![
+///     ModuleError {
+///         line: 3,
+///         kind: ModuleErrorKind::Arity { name: String::from("list"), expected: 1, found: 2 },
+///     },
+///     ModuleError { line: 4, kind: ModuleErrorKind::Syntax },
+/// ]);
+/// ```
+///
+/// Because `head` failed to parse, its later declaration is not a duplicate.
+///
+/// [`TypeSchema`]: enum.TypeSchema.html
+/// [`TypeSchema::parse_named`]: enum.TypeSchema.html#method.parse_named
+/// [`DataDecl::parse`]: struct.DataDecl.html#method.parse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module<N: Name = &'static str> {
+    signatures: IndexMap<String, TypeSchema<N>>,
+    aliases: IndexMap<String, TypeSchema<N>>,
+    arities: IndexMap<String, usize>,
+    data_types: IndexMap<String, DataDecl<N>>,
+}
+impl<N: Name> Default for Module<N> {
+    fn default() -> Self {
+        Module {
+            signatures: IndexMap::new(),
+            aliases: IndexMap::new(),
+            arities: IndexMap::new(),
+            data_types: IndexMap::new(),
+        }
+    }
+}
+impl<N: Name> Module<N> {
+    /// Parse a module from a string, reporting every invalid line.
+    pub fn parse(s: &str) -> Result<Module<N>, Vec<ModuleError>> {
+        let mut module = Module::default();
+        let mut errors = vec![];
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Err(kind) = module.declare(line) {
+                errors.push(ModuleError { line: i + 1, kind });
+            }
+        }
+        if errors.is_empty() {
+            Ok(module)
+        } else {
+            Err(errors)
+        }
+    }
+    /// The signatures declared by the module, in order of declaration.
+    pub fn signatures(&self) -> &IndexMap<String, TypeSchema<N>> {
+        &self.signatures
+    }
+    /// The aliases declared by the module. Each is quantified over its
+    /// parameters, in order.
+    pub fn aliases(&self) -> &IndexMap<String, TypeSchema<N>> {
+        &self.aliases
+    }
+    /// The number of arguments taken by each declared constructor.
+    pub fn arities(&self) -> &IndexMap<String, usize> {
+        &self.arities
+    }
+    /// The data types declared by the module, with their fields expanded.
+    pub fn data_types(&self) -> &IndexMap<String, DataDecl<N>> {
+        &self.data_types
+    }
+    fn declare(&mut self, line: &str) -> Result<(), ModuleErrorKind> {
+        if let Some(rest) = keyword(line, "type") {
+            let mut parts = rest.splitn(2, '=');
+            let (head, body) = (parts.next().unwrap(), parts.next());
+            let (name, params) = head_of(head)?;
+            let body = body.ok_or(ModuleErrorKind::Syntax)?.trim();
+            self.undeclared(&name)?;
+            let body = if params.is_empty() {
+                TypeSchema::parse(body)?
+            } else {
+                TypeSchema::parse_named(&format!("∀{}. {}", params.join(" "), body))?.0
+            };
+            let body = self.expand_schema(body)?;
+            self.aliases.insert(name, body);
+        } else if let Some(rest) = keyword(line, "constructor") {
+            let (name, params) = head_of(rest)?;
+            self.undeclared(&name)?;
+            self.arities.insert(name, params.len());
+        } else if keyword(line, "data").is_some() {
+            let mut decl: DataDecl<N> = DataDecl::parse(line)?;
+            let name = decl.name.show();
+            self.undeclared(&name)?;
+            for (i, c) in decl.constructors.iter().enumerate() {
+                let earlier = &decl.constructors[..i];
+                if self.signatures.contains_key(&c.name) || earlier.iter().any(|e| e.name == c.name)
+                {
+                    return Err(ModuleErrorKind::Duplicate(c.name.clone()));
+                }
+            }
+            // the type may be recursive, so its arity is checked in its fields
+            self.arities.insert(name.clone(), decl.arity());
+            let expanded = decl
+                .constructors
+                .iter()
+                .map(|c| c.fields.iter().map(|f| self.expand(f)).collect())
+                .collect::<Result<Vec<Vec<_>>, _>>();
+            match expanded {
+                Ok(expanded) => {
+                    for (c, fields) in decl.constructors.iter_mut().zip(expanded) {
+                        c.fields = fields;
+                    }
+                }
+                Err(e) => {
+                    self.arities.pop();
+                    return Err(e);
+                }
+            }
+            self.signatures.extend(decl.constructor_schemas());
+            self.data_types.insert(name, decl);
+        } else {
+            let mut parts = line.splitn(2, ':');
+            let (name, schema) = (parts.next().unwrap().trim(), parts.next());
+            let schema = schema.ok_or(ModuleErrorKind::Syntax)?.trim();
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(ModuleErrorKind::Syntax);
+            }
+            if self.signatures.contains_key(name) {
+                return Err(ModuleErrorKind::Duplicate(name.to_string()));
+            }
+            let schema = self.expand_schema(TypeSchema::parse_named(schema)?.0)?;
+            self.signatures.insert(name.to_string(), schema);
+        }
+        Ok(())
+    }
+    /// Ensure `name` is not yet an alias or a constructor.
+    fn undeclared(&self, name: &str) -> Result<(), ModuleErrorKind> {
+        if self.aliases.contains_key(name) || self.arities.contains_key(name) {
+            Err(ModuleErrorKind::Duplicate(name.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+    fn expand_schema(&self, schema: TypeSchema<N>) -> Result<TypeSchema<N>, ModuleErrorKind> {
+        match schema {
+            TypeSchema::Monotype(t) => Ok(TypeSchema::Monotype(self.expand(&t)?)),
+            TypeSchema::Polytype { variable, body } => Ok(TypeSchema::Polytype {
+                variable,
+                body: Box::new(self.expand_schema(*body)?),
+            }),
+        }
+    }
+    /// Expand aliases and check constructor arities.
+    fn expand(&self, t: &Type<N>) -> Result<Type<N>, ModuleErrorKind> {
+        match *t {
+            Type::Variable(v) => Ok(Type::Variable(v)),
+            Type::Constructed(ref name, ref args) => {
+                let args = args
+                    .iter()
+                    .map(|t| self.expand(t))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let shown = name.show();
+                let arity = self
+                    .aliases
+                    .get(&shown)
+                    .map(|alias| alias.bound_vars().len())
+                    .or_else(|| self.arities.get(&shown).cloned());
+                match arity {
+                    Some(expected) if expected != args.len() => Err(ModuleErrorKind::Arity {
+                        name: shown,
+                        expected,
+                        found: args.len(),
+                    }),
+                    _ => match self.aliases.get(&shown) {
+                        Some(alias) => {
+                            let substitution: HashMap<_, _> =
+                                alias.bound_vars().into_iter().zip(args).collect();
+                            let mut body = alias;
+                            while let TypeSchema::Polytype { body: ref b, .. } = *body {
+                                body = b;
+                            }
+                            match *body {
+                                TypeSchema::Monotype(ref t) => Ok(t.substitute(&substitution)),
+                                TypeSchema::Polytype { .. } => unreachable!(),
+                            }
+                        }
+                        None => Ok(Type::Constructed(name.clone(), args)),
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// If `line` starts with the keyword `word`, the rest of the line.
+pub(crate) fn keyword<'a>(line: &'a str, word: &str) -> Option<&'a str> {
+    if line.starts_with(word) && line[word.len()..].starts_with(char::is_whitespace) {
+        Some(&line[word.len()..])
+    } else {
+        None
+    }
+}
+
+/// Split a declaration head such as `pair(a, b)` into its name and parameters.
+pub(crate) fn head_of(head: &str) -> Result<(String, Vec<String>), ModuleErrorKind> {
+    let head = head.trim();
+    let (name, params) = match head.find('(') {
+        None => (head, vec![]),
+        Some(i) if head.ends_with(')') => {
+            let params = head[i + 1..head.len() - 1]
+                .split(',')
+                .map(|p| p.trim().to_string())
+                .collect();
+            (head[..i].trim(), params)
+        }
+        Some(_) => return Err(ModuleErrorKind::Syntax),
+    };
+    let is_ident = |s: &str| !s.is_empty() && s.chars().all(char::is_alphanumeric);
+    if is_ident(name) && params.iter().all(|p: &String| is_ident(p)) {
+        Ok((name.to_string(), params))
+    } else {
+        Err(ModuleErrorKind::Syntax)
+    }
+}
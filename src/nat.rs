@@ -0,0 +1,273 @@
+use crate::theory::{as_number, number, Unifier};
+use crate::types::Shown;
+use crate::{Name, Type, UnificationError};
+
+/// The atoms of a type-level natural with their coefficients, and its
+/// constant.
+type Sum<N> = (Vec<(Type<N>, usize)>, usize);
+
+impl<N: Name> Type<N> {
+    /// Construct the type-level natural number `n`, for sized containers
+    /// like `vec(3, int)`. See [`Type::nat_sum`].
+    ///
+    /// # Panics
+    ///
+    /// If the [`Name`] has no [`nat`] or [`number`] constructor.
+    ///
+    /// [`Type::nat_sum`]: #method.nat_sum
+    /// [`Name`]: trait.Name.html
+    /// [`nat`]: trait.Name.html#method.nat
+    /// [`number`]: trait.Name.html#method.number
+    pub fn nat(n: usize) -> Type<N> {
+        Type::nat_sum(vec![], n)
+    }
+    /// Construct the type-level natural `t1 + t2 + … + constant`, where each
+    /// term is a variable, another type-level natural, or a type standing for
+    /// an unknown but fixed number.
+    ///
+    /// Naturals are unified modulo linear arithmetic, so `t0 + 1` unifies
+    /// with `3` by taking `t0` to be `2`, and `t0 + t1` with `t1 + t0`.
+    /// Equations with one unknown term on a side, or with a constant on a
+    /// side, are solved; others fail with [`UnificationError::Unsolvable`],
+    /// and contradictions (e.g. `t0 + 1 = 0`) with
+    /// [`UnificationError::Failure`]. Naturals are displayed normalized, but
+    /// are only equal as types if built alike.
+    ///
+    /// # Panics
+    ///
+    /// If the [`Name`] has no [`nat`] or [`number`] constructor.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp, Context, Type};
+    /// // append: ∀n m α. vec(n, α) → vec(m, α) → vec(n+m, α)
+    /// let append = ptp!(0, 1, 2; @arrow[
+    ///     tp!(vec(tp!(0), tp!(2))),
+    ///     tp!(vec(tp!(1), tp!(2))),
+    ///     tp!(vec(Type::nat_sum(vec![tp!(0), tp!(1)], 0), tp!(2))),
+    /// ]);
+    /// assert_eq!(append.to_string(), "∀t0. ∀t1. ∀t2. vec(t0,t2) → vec(t1,t2) → vec(t0+t1,t2)");
+    ///
+    /// let mut ctx = Context::default();
+    /// let append = append.instantiate(&mut ctx);
+    /// let n = ctx.new_variable();
+    /// let ret = ctx.new_variable();
+    /// let xs = tp!(vec(Type::nat_sum(vec![n.clone()], 1), tp!(int)));
+    /// let ys = tp!(vec(Type::nat(2), tp!(int)));
+    /// ctx.unify(&append, &tp!(@arrow[xs, ys, ret.clone()])).expect("unifies");
+    /// assert_eq!(ret.apply(&ctx).to_string(), format!("vec({}+3,int)", n));
+    ///
+    /// // the result has at least three elements
+    /// assert!(ctx.unify(&ret, &tp!(vec(Type::nat(2), tp!(int)))).is_err());
+    /// ctx.unify(&ret, &tp!(vec(Type::nat(5), tp!(int)))).expect("unifies");
+    /// assert_eq!(n.apply(&ctx), Type::nat(2));
+    /// ```
+    ///
+    /// [`UnificationError::Unsolvable`]: enum.UnificationError.html#variant.Unsolvable
+    /// [`UnificationError::Failure`]: enum.UnificationError.html#variant.Failure
+    /// [`Name`]: trait.Name.html
+    /// [`nat`]: trait.Name.html#method.nat
+    /// [`number`]: trait.Name.html#method.number
+    pub fn nat_sum(terms: Vec<Type<N>>, constant: usize) -> Type<N> {
+        let terms = terms.into_iter().map(|t| (t, 1)).collect();
+        Type::try_nat_sum(terms, constant).expect("the name has no constructor for naturals")
+    }
+    /// The natural `k1·t1 + k2·t2 + … + constant`, combining equal terms.
+    /// The constant and coefficients are kept as [`Name::number`]s, with each
+    /// term under its coefficient: `+(constant, +(k1, t1), +(k2, t2), …)`.
+    ///
+    /// [`Name::number`]: trait.Name.html#method.number
+    pub(crate) fn try_nat_sum(terms: Vec<(Type<N>, usize)>, constant: usize) -> Option<Type<N>> {
+        let name = N::nat()?;
+        let mut combined: Vec<(Type<N>, usize)> = vec![];
+        for (t, k) in terms {
+            match combined.iter_mut().find(|(u, _)| *u == t) {
+                Some((_, j)) => *j = j.checked_add(k)?,
+                None => combined.push((t, k)),
+            }
+        }
+        let mut args = vec![number(constant)?];
+        for (t, k) in combined {
+            if k > 0 {
+                args.push(Type::Constructed(name.clone(), vec![number(k)?, t]));
+            }
+        }
+        Some(Type::Constructed(name, args))
+    }
+    /// If the type is a type-level natural, its terms with their
+    /// coefficients, normalized so each appears once in order of appearance,
+    /// and its constant.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Type};
+    /// let t = Type::nat_sum(vec![tp!(0), Type::nat_sum(vec![tp!(0)], 2)], 1);
+    /// assert_eq!(t.as_nat(), Some((vec![(&tp!(0), 2)], 3)));
+    /// assert_eq!(t.to_string(), "2t0+3");
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn as_nat(&self) -> Option<(Vec<(&Type<N>, usize)>, usize)> {
+        let mut terms: Vec<(&Type<N>, usize)> = vec![];
+        let mut constant: usize = 0;
+        let mut stack = vec![(self.nat_parts()?, 1)];
+        while let Some(((c, parts), scale)) = stack.pop() {
+            constant = constant.checked_add(c.checked_mul(scale)?)?;
+            for (k, t) in parts {
+                let k = k.checked_mul(scale)?;
+                match t.nat_parts() {
+                    Some(nested) => stack.push((nested, k)),
+                    None => match terms.iter_mut().find(|(u, _)| *u == t) {
+                        Some((_, j)) => *j = j.checked_add(k)?,
+                        None => terms.push((t, k)),
+                    },
+                }
+            }
+        }
+        terms.retain(|&(_, k)| k > 0);
+        Some((terms, constant))
+    }
+    /// The constant and the terms, under their coefficients, of a type-level
+    /// natural.
+    #[allow(clippy::type_complexity)]
+    fn nat_parts(&self) -> Option<(usize, Vec<(usize, &Type<N>)>)> {
+        match *self {
+            Type::Constructed(ref n, ref args) if is_nat(n) && !args.is_empty() => {
+                let terms = args[1..]
+                    .iter()
+                    .map(|term| match *term {
+                        Type::Constructed(ref n, ref term) if is_nat(n) && term.len() == 2 => {
+                            Some((as_number(&term[0])?, &term[1]))
+                        }
+                        _ => None,
+                    })
+                    .collect::<Option<_>>()?;
+                Some((as_number(&args[0])?, terms))
+            }
+            _ => None,
+        }
+    }
+    /// Show specifically for type-level naturals
+    pub(crate) fn nat_show<'a>(
+        terms: &[(&'a Type<N>, usize)],
+        constant: usize,
+    ) -> Vec<Shown<'a, N>> {
+        let mut parts = vec![];
+        for &(t, k) in terms {
+            if !parts.is_empty() {
+                parts.push(Shown::Text(String::from("+")));
+            }
+            if k != 1 {
+                parts.push(Shown::Text(k.to_string()));
+            }
+            parts.push(Shown::Type(t, true));
+        }
+        if constant > 0 || parts.is_empty() {
+            if !parts.is_empty() {
+                parts.push(Shown::Text(String::from("+")));
+            }
+            parts.push(Shown::Text(constant.to_string()));
+        }
+        parts
+    }
+}
+
+pub(crate) fn is_nat<N: Name>(name: &N) -> bool {
+    N::nat().as_ref() == Some(name)
+}
+
+/// The terms and constant of `t`, which is a type-level natural or a term.
+fn sum<N: Name>(t: &Type<N>) -> Sum<N> {
+    match t.as_nat() {
+        Some((terms, constant)) => {
+            let terms = terms.into_iter().map(|(t, k)| (t.clone(), k)).collect();
+            (terms, constant)
+        }
+        None => (vec![(t.clone(), 1)], 0),
+    }
+}
+
+/// The natural `Σ terms + constant`.
+fn from_sum<N: Name>((terms, constant): Sum<N>) -> Type<N> {
+    Type::try_nat_sum(terms, constant).expect("the name has no constructor for naturals")
+}
+
+/// Unify the type-level naturals `name(a1…)` and `name(a2…)`, after
+/// cancelling the terms and constants they share: a variable alone on one
+/// side is taken to be the other side, and variables summing to a constant
+/// are solved if there is just one, or if the constant is `0`.
+pub(crate) fn unify_nats<N: Name, U: Unifier<N>>(
+    ctx: &mut U,
+    name: N,
+    a1: Vec<Type<N>>,
+    a2: Vec<Type<N>>,
+) -> Result<(), UnificationError<N>> {
+    let n1 = ctx.apply(&Type::Constructed(name.clone(), a1));
+    let n2 = ctx.apply(&Type::Constructed(name, a2));
+    if n1.as_nat().is_none() || n2.as_nat().is_none() {
+        return Err(UnificationError::Failure(n1, n2));
+    }
+    let (mut s1, mut s2) = (sum(&n1), sum(&n2));
+    for (t, k1) in &mut s1.0 {
+        if let Some((_, k2)) = s2.0.iter_mut().find(|(u, _)| u == t) {
+            let shared = (*k1).min(*k2);
+            *k1 -= shared;
+            *k2 -= shared;
+        }
+    }
+    s1.0.retain(|&(_, k)| k > 0);
+    s2.0.retain(|&(_, k)| k > 0);
+    let shared = s1.1.min(s2.1);
+    s1.1 -= shared;
+    s2.1 -= shared;
+    for (s, other) in &[(&s1, &s2), (&s2, &s1)] {
+        if let ([(Type::Variable(v), 1)], 0) = (&s.0[..], s.1) {
+            let t = from_sum((*other).clone());
+            if t.occurs(*v) {
+                return Err(UnificationError::Occurs(*v));
+            }
+            ctx.extend(*v, t);
+            return Ok(());
+        }
+    }
+    let is_var = |(t, _): &(Type<N>, usize)| matches!(*t, Type::Variable(_));
+    for (s, other) in &[(&s1, &s2), (&s2, &s1)] {
+        if !other.0.is_empty() {
+            continue;
+        }
+        // s must sum to the constant c
+        let c = other.1;
+        if s.1 > c || s.0.is_empty() {
+            return if s.1 == c {
+                Ok(())
+            } else {
+                Err(UnificationError::Failure(n1, n2))
+            };
+        }
+        if !s.0.iter().all(is_var) {
+            break;
+        }
+        match (&s.0[..], c) {
+            (_, 0) => {
+                for (t, _) in &s.0 {
+                    if let Type::Variable(v) = *t {
+                        ctx.extend(v, Type::nat(0));
+                    }
+                }
+                return Ok(());
+            }
+            ([(Type::Variable(v), k)], c) if c % k == 0 => {
+                ctx.extend(*v, Type::nat(c / k));
+                return Ok(());
+            }
+            ([_], _) => return Err(UnificationError::Failure(n1, n2)),
+            _ => return Err(UnificationError::Unsolvable(n1, n2)),
+        }
+    }
+    if s1.0.iter().chain(&s2.0).any(is_var) {
+        Err(UnificationError::Unsolvable(n1, n2))
+    } else {
+        Err(UnificationError::Failure(n1, n2))
+    }
+}
@@ -1,16 +1,19 @@
 use nom::{alpha, digit, types::CompleteStr};
 use nom::{
-    alt, alt_sep, call_m, do_parse, error_position, expr_res, map, map_res, method, opt, sep,
-    separated_list, tag, wrap_sep, ws,
+    alt, alt_sep, call_m, do_parse, error_position, expr_opt, expr_res, many1, map, map_res,
+    method, opt, pair, recognize, sep, separated_list, separated_nonempty_list, tag, tuple,
+    tuple_parser, wrap_sep, ws,
 };
 
 #[allow(unused_imports)]
 use nom::call; // FIXME see https://github.com/Geal/nom/pull/871
 
+use indexmap::IndexMap;
+use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::num::ParseIntError;
 
-use crate::{Name, Type, TypeSchema};
+use crate::{Context, Name, Type, TypeSchema, Variable};
 
 #[derive(Debug)]
 /// A failed parse.
@@ -30,9 +33,46 @@ pub fn parse_type<N: Name>(input: &str) -> Result<Type<N>, ParseError> {
         _ => Err(ParseError),
     }
 }
+/// Parse a type whose wildcards `_` become fresh variables of `ctx`, in order
+/// of appearance, returning them too.
+pub fn parse_partial_type<N: Name>(
+    input: &str,
+    ctx: &mut Context<N>,
+) -> Result<(Type<N>, Vec<Variable>), ParseError> {
+    let mut parser = Parser {
+        wildcards: Some(vec![]),
+        ..Parser::default()
+    };
+    let t = match parser.monotype(CompleteStr(input)) {
+        (p, Ok((_, t))) => {
+            parser = p;
+            t
+        }
+        _ => return Err(ParseError),
+    };
+    let placeholders = parser.wildcards.unwrap_or_default();
+    let mut fresh = HashMap::new();
+    let mut wildcards = vec![];
+    for v in t.vars_in_order() {
+        if placeholders.contains(&v) {
+            let tp = ctx.new_variable();
+            if let Type::Variable(w) = tp {
+                wildcards.push(w);
+            }
+            fresh.insert(v, tp);
+        }
+    }
+    let t = t.map_vars(|v| fresh.remove(&v).unwrap_or(Type::Variable(v)));
+    Ok((t, wildcards))
+}
 pub fn parse_typeschema<N: Name>(input: &str) -> Result<TypeSchema<N>, ParseError> {
-    match Parser::default().polytype(CompleteStr(input)).1 {
-        Ok((_, t)) => Ok(t),
+    parse_typeschema_named(input).map(|(t, _)| t)
+}
+pub fn parse_typeschema_named<N: Name>(
+    input: &str,
+) -> Result<(TypeSchema<N>, IndexMap<String, Variable>), ParseError> {
+    match Parser::default().polytype(CompleteStr(input)) {
+        (parser, Ok((_, t))) => Ok(parser.finish(t)),
         _ => Err(ParseError),
     }
 }
@@ -41,19 +81,137 @@ fn nom_usize(inp: CompleteStr<'_>) -> Result<usize, ParseIntError> {
     inp.parse()
 }
 
+/// The type-level natural summing `terms`, each a coefficient and a variable
+/// or a constant. A lone variable is left to be parsed as such.
+fn nat_sum<N: Name>(terms: Vec<(usize, Option<Type<N>>)>) -> Option<Type<N>> {
+    if let [(1, Some(_))] = terms[..] {
+        return None;
+    }
+    let mut constant: usize = 0;
+    let mut vars = vec![];
+    for (k, t) in terms {
+        match t {
+            Some(t) => vars.push((t, k)),
+            None => constant = constant.checked_add(k)?,
+        }
+    }
+    Type::try_nat_sum(vars, constant)
+}
+
+/// Like `call_m!`, but if the method fails, forget the variables it named and
+/// the wildcards it made, so that `alt!` and `opt!` backtrack cleanly.
+macro_rules! try_m (
+    ($i:expr, $self_:ident.$method:ident) => (
+        {
+            let checkpoint = $self_.checkpoint();
+            let (tmp, res) = $self_.$method($i);
+            $self_ = tmp;
+            if res.is_err() {
+                $self_.backtrack(checkpoint);
+            }
+            res
+        }
+    );
+);
+
 // hack for polymorphism with nom
-pub struct Parser<N: Name>(PhantomData<N>);
+pub struct Parser<N: Name> {
+    /// Named variables bound so far, mapped to placeholders until `finish`.
+    names: IndexMap<String, Variable>,
+    /// Placeholders for the wildcards parsed so far, if wildcards are allowed.
+    wildcards: Option<Vec<Variable>>,
+    _marker: PhantomData<N>,
+}
 impl<N: Name> Default for Parser<N> {
     fn default() -> Self {
-        Parser(PhantomData)
+        Parser {
+            names: IndexMap::new(),
+            wildcards: None,
+            _marker: PhantomData,
+        }
     }
 }
 impl<N: Name> Parser<N> {
+    /// Named variables are parsed into placeholders counting down from
+    /// `Variable::MAX`, so they cannot clash with numbered variables.
+    fn placeholder(&mut self, name: CompleteStr<'_>) -> Variable {
+        let fresh = self.fresh_placeholder();
+        *self.names.entry(name.0.to_string()).or_insert(fresh)
+    }
+    /// A placeholder for a wildcard, if they are allowed.
+    fn wildcard_placeholder(&mut self) -> Option<Variable> {
+        let fresh = self.fresh_placeholder();
+        self.wildcards.as_mut()?.push(fresh);
+        Some(fresh)
+    }
+    /// The number of names and wildcards so far.
+    fn checkpoint(&self) -> (usize, usize) {
+        let wildcards = self.wildcards.as_ref().map_or(0, Vec::len);
+        (self.names.len(), wildcards)
+    }
+    /// Forget the names and wildcards added since `checkpoint`.
+    fn backtrack(&mut self, (names, wildcards): (usize, usize)) {
+        self.names.truncate(names);
+        if let Some(ref mut placeholders) = self.wildcards {
+            placeholders.truncate(wildcards);
+        }
+    }
+    fn fresh_placeholder(&self) -> Variable {
+        let wildcards = self.wildcards.as_ref().map_or(0, Vec::len);
+        Variable::MAX - self.names.len() - wildcards
+    }
+    /// Replace placeholders with variables following the largest numbered one.
+    fn finish(self, mut t: TypeSchema<N>) -> (TypeSchema<N>, IndexMap<String, Variable>) {
+        if self.names.is_empty() {
+            return (t, self.names);
+        }
+        let placeholders: Vec<Variable> = self.names.values().cloned().collect();
+        let start = t
+            .bound_vars()
+            .into_iter()
+            .chain(t.free_vars())
+            .filter(|v| !placeholders.contains(v))
+            .max()
+            .map_or(0, |v| v + 1);
+        let renaming: HashMap<Variable, Variable> = placeholders
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (v, start + i))
+            .collect();
+        t.rename_vars(&renaming);
+        let names = self
+            .names
+            .into_iter()
+            .map(|(name, v)| (name, renaming[&v]))
+            .collect();
+        (t, names)
+    }
     method!(
         var<Parser<N>, CompleteStr<'_>, Type<N>>,
         self,
         do_parse!(tag!("t") >> num: map_res!(digit, nom_usize) >> (Type::Variable(num)))
     );
+    method!(
+        ident<Parser<N>, CompleteStr<'_>, CompleteStr<'_>>,
+        self,
+        recognize!(pair!(alpha, opt!(digit)))
+    );
+    method!(named_var<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           do_parse!(
+               name: call_m!(self.ident) >>
+               v: expr_opt!(self.names.get(name.0).cloned()) >>
+               (Type::Variable(v)))
+    );
+    method!(wildcard<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           do_parse!(
+               tag!("_") >>
+               v: expr_opt!(self.wildcard_placeholder()) >>
+               (Type::Variable(v)))
+    );
+    method!(binder<Parser<N>, CompleteStr<'_>, Variable>, mut self,
+           alt!(do_parse!(tag!("t") >> num: map_res!(digit, nom_usize) >> (num)) |
+                do_parse!(name: call_m!(self.ident) >> (self.placeholder(name))))
+    );
     method!(
         constructed_simple<Parser<N>, CompleteStr<'_>, Type<N>>,
         self,
@@ -63,6 +221,11 @@ impl<N: Name> Parser<N> {
                 >> (Type::Constructed(name, vec![]))
         )
     );
+    method!(
+        dynamic<Parser<N>, CompleteStr<'_>, Type<N>>,
+        self,
+        do_parse!(tag!("?") >> name: expr_opt!(N::dynamic()) >> (Type::Constructed(name, vec![])))
+    );
     method!(constructed_complex<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
            do_parse!(
                name_raw: alpha >>
@@ -72,15 +235,80 @@ impl<N: Name> Parser<N> {
                tag!(")") >>
                (Type::Constructed(name, args)))
     );
+    method!(list<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           do_parse!(
+               name: expr_opt!(N::list()) >>
+               tag!("[") >>
+               arg: ws!(call_m!(self.monotype)) >>
+               tag!("]") >>
+               (Type::Constructed(name, vec![arg])))
+    );
+    method!(tuple<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           do_parse!(
+               name: expr_opt!(N::tuple()) >>
+               tag!("(") >>
+               first: ws!(call_m!(self.monotype)) >>
+               tag!(",") >>
+               rest: separated_nonempty_list!(tag!(","), ws!(call_m!(self.monotype))) >>
+               tag!(")") >>
+               (Type::Constructed(name, Some(first).into_iter().chain(rest).collect())))
+    );
     method!(arrow<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
            do_parse!(
-               alpha: ws!(alt!(call_m!(self.parenthetical) |
-                               call_m!(self.var) |
-                               call_m!(self.constructed_complex) |
-                               call_m!(self.constructed_simple))) >>
+               alpha: ws!(alt!(try_m!(self.parenthetical) |
+                               try_m!(self.nat) |
+                               try_m!(self.tuple) |
+                               try_m!(self.list) |
+                               try_m!(self.var) |
+                               try_m!(self.constructed_complex) |
+                               try_m!(self.named_var) |
+                               try_m!(self.dynamic) |
+                               try_m!(self.wildcard) |
+                               try_m!(self.constructed_simple))) >>
                alt!(tag!("→") | tag!("->")) >>
+               effects: opt!(try_m!(self.effects)) >>
                beta: ws!(call_m!(self.monotype)) >>
-               (Type::arrow(alpha, beta)))
+               (match effects {
+                   Some(effects) => Type::effect_arrow(alpha, effects, beta),
+                   None => Type::arrow(alpha, beta),
+               }))
+    );
+    method!(label<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           alt!(try_m!(self.constructed_complex) | try_m!(self.constructed_simple))
+    );
+    method!(row_tail<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           alt!(call_m!(self.var) | call_m!(self.named_var))
+    );
+    method!(effects<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           alt!(do_parse!(
+                    tag!("{") >>
+                    tail: ws!(call_m!(self.row_tail)) >>
+                    tag!("}") >>
+                    row: expr_opt!(Type::try_row(vec![], Some(tail))) >>
+                    (row)) |
+                do_parse!(
+                    tag!("{") >>
+                    labels: separated_list!(tag!(","), ws!(call_m!(self.label))) >>
+                    tail: opt!(do_parse!(
+                        tag!("|") >>
+                        tail: ws!(call_m!(self.row_tail)) >>
+                        (tail))) >>
+                    tag!("}") >>
+                    row: expr_opt!(Type::try_row(labels, tail)) >>
+                    (row)))
+    );
+    method!(nat_term<Parser<N>, CompleteStr<'_>, (usize, Option<Type<N>>)>, mut self,
+           alt!(do_parse!(
+                    k: opt!(map_res!(digit, nom_usize)) >>
+                    v: alt!(call_m!(self.var) | call_m!(self.named_var)) >>
+                    ((k.unwrap_or(1), Some(v)))) |
+                map!(map_res!(digit, nom_usize), |k| (k, None)))
+    );
+    method!(nat<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
+           do_parse!(
+               terms: separated_nonempty_list!(ws!(tag!("+")), call_m!(self.nat_term)) >>
+               nat: expr_opt!(nat_sum(terms)) >>
+               (nat))
     );
     method!(parenthetical<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
            do_parse!(
@@ -89,24 +317,46 @@ impl<N: Name> Parser<N> {
                tag!(")") >>
                (interior))
     );
+    method!(
+        quantifier<Parser<N>, CompleteStr<'_>, CompleteStr<'_>>,
+        self,
+        alt!(tag!("∀") | tag!("forall"))
+    );
     method!(binding<Parser<N>, CompleteStr<'_>, TypeSchema<N>>, mut self,
            do_parse!(
-               opt!(tag!("∀")) >>
-               tag!("t") >>
+               opt!(call_m!(self.quantifier)) >>
+               ws!(tag!("t")) >>
                variable: map_res!(digit, nom_usize) >>
                ws!(tag!(".")) >>
                body: map!(call_m!(self.polytype), Box::new) >>
                (TypeSchema::Polytype{variable, body}))
     );
+    method!(named_binding<Parser<N>, CompleteStr<'_>, TypeSchema<N>>, mut self,
+           do_parse!(
+               call_m!(self.quantifier) >>
+               variables: many1!(ws!(call_m!(self.binder))) >>
+               tag!(".") >>
+               body: ws!(call_m!(self.polytype)) >>
+               (variables.into_iter().rev().fold(body, |body, variable| {
+                   TypeSchema::Polytype { variable, body: Box::new(body) }
+               })))
+    );
     method!(monotype<Parser<N>, CompleteStr<'_>, Type<N>>, mut self,
-           alt!(call_m!(self.arrow) |
-                call_m!(self.var) |
-                call_m!(self.constructed_complex) |
-                call_m!(self.constructed_simple))
+           alt!(try_m!(self.arrow) |
+                try_m!(self.nat) |
+                try_m!(self.tuple) |
+                try_m!(self.list) |
+                try_m!(self.var) |
+                try_m!(self.constructed_complex) |
+                try_m!(self.named_var) |
+                try_m!(self.dynamic) |
+                try_m!(self.wildcard) |
+                try_m!(self.constructed_simple))
     );
     method!(polytype<Parser<N>, CompleteStr<'_>, TypeSchema<N>>, mut self,
-        alt!(call_m!(self.binding) |
-             map!(call_m!(self.monotype), TypeSchema::Monotype))
+        alt!(try_m!(self.binding) |
+             try_m!(self.named_binding) |
+             map!(try_m!(self.monotype), TypeSchema::Monotype))
     );
 }
 impl<N: Name> TypeSchema<N> {
@@ -114,7 +364,7 @@ impl<N: Name> TypeSchema<N> {
     /// This is a **leaky** operation and should be avoided wherever possible:
     /// names of constructed types will remain until program termination.
     ///
-    /// The "for-all" `∀` is optional.
+    /// The "for-all" `∀` (or `forall`) is optional.
     ///
     /// # Examples
     ///
@@ -135,6 +385,36 @@ impl<N: Name> TypeSchema<N> {
     pub fn parse(s: &str) -> Result<TypeSchema<N>, ParseError> {
         parse_typeschema(s)
     }
+    /// Parse a [`TypeSchema`] whose quantified variables may be named, e.g.
+    /// `∀a b. (a → b) → list(a) → list(b)`. Several variables may be bound by
+    /// a single `∀` (or `forall`), which is required here.
+    ///
+    /// Each name is mapped to a fresh [`Variable`] numbered after the largest
+    /// `tN` variable in the string. The returned table maps names to their
+    /// variables in the order they were bound. Names are only variables within
+    /// the schema that binds them; elsewhere they are parsed as constructors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp, TypeSchema, VariableNaming};
+    /// let s = "∀a b. (a → b) → list(a) → list(b)";
+    /// let (t, names) = TypeSchema::<&'static str>::parse_named(s).expect("valid type");
+    /// assert_eq!(t, ptp!(0, 1; @arrow[
+    ///     tp!(@arrow[tp!(0), tp!(1)]),
+    ///     tp!(list(tp!(0))),
+    ///     tp!(list(tp!(1))),
+    /// ]));
+    /// assert_eq!(names["a"], 0);
+    /// assert_eq!(names["b"], 1);
+    /// assert_eq!(t.show_named(VariableNaming::Latin), s);
+    /// ```
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`Variable`]: type.Variable.html
+    pub fn parse_named(s: &str) -> Result<(TypeSchema<N>, IndexMap<String, Variable>), ParseError> {
+        parse_typeschema_named(s)
+    }
 }
 impl<N: Name> Type<N> {
     /// Parse a type from a string. This round-trips with [`Display`]. This is a
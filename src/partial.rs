@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(feature = "parser")]
+use crate::ParseError;
+use crate::{Context, Name, PrettyConfig, Type, Variable};
+
+/// A partial type signature, such as `list(_) → _`, whose wildcards `_` are
+/// left for inference to fill in.
+///
+/// Each wildcard is a fresh variable of the [`Context`] the signature is
+/// made for, so the signature is used by unifying it like any other type.
+/// Afterwards, [`resolved`] gives what each wildcard became, and
+/// [`display_solved`] shows the signature with them filled in.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{tp, Context, PartialSignature, PrettyConfig};
+/// let mut ctx = Context::default();
+/// let sig = PartialSignature::parse("list(_) → _ → _", &mut ctx).expect("parses");
+/// assert_eq!(sig.to_string(), "list(_) → _ → _");
+///
+/// // the inferred type of the definition
+/// let x = ctx.new_variable();
+/// let inferred = tp!(@arrow[tp!(list(tp!(int))), x, tp!(bool)]);
+/// ctx.unify(sig.signature(), &inferred).expect("unifies");
+/// assert_eq!(sig.resolved(&ctx), vec![tp!(int), tp!(3), tp!(bool)]);
+/// assert_eq!(
+///     sig.display_solved(&ctx, &PrettyConfig::default()),
+///     "list(int) → t3 → bool",
+/// );
+/// ```
+///
+/// [`Context`]: struct.Context.html
+/// [`resolved`]: #method.resolved
+/// [`display_solved`]: #method.display_solved
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSignature<N: Name = &'static str> {
+    signature: Type<N>,
+    wildcards: Vec<Variable>,
+}
+impl<N: Name> PartialSignature<N> {
+    /// Parse a partial signature, making each wildcard `_` a fresh variable
+    /// of `ctx` with [`Context::new_variable`], in order of appearance. Other
+    /// variables (e.g. `t0`) are taken as they are.
+    ///
+    /// [`Context::new_variable`]: struct.Context.html#method.new_variable
+    #[cfg(feature = "parser")]
+    pub fn parse(s: &str, ctx: &mut Context<N>) -> Result<PartialSignature<N>, ParseError> {
+        let (signature, wildcards) = crate::parser::parse_partial_type(s, ctx)?;
+        Ok(PartialSignature {
+            signature,
+            wildcards,
+        })
+    }
+    /// The signature, with a fresh variable for each wildcard.
+    pub fn signature(&self) -> &Type<N> {
+        &self.signature
+    }
+    /// The variables made for the wildcards, in order of appearance.
+    pub fn wildcards(&self) -> &[Variable] {
+        &self.wildcards
+    }
+    /// What each wildcard resolved to under `ctx`, in order of appearance.
+    pub fn resolved(&self, ctx: &Context<N>) -> Vec<Type<N>> {
+        self.wildcards
+            .iter()
+            .map(|&v| Type::Variable(v).apply(ctx))
+            .collect()
+    }
+    /// Show the signature with the wildcards filled in as resolved under
+    /// `ctx`, according to `config`. Wildcards which are still unsolved are
+    /// shown as `_`, unless they now occur more than once, e.g. when two
+    /// wildcards were unified, in which case they are named like any other
+    /// variable so that they are shown to be the same.
+    pub fn display_solved(&self, ctx: &Context<N>, config: &PrettyConfig) -> String {
+        let solved = self.signature.apply(ctx);
+        let mut occurrences = vec![];
+        solved.vars_internal(&mut occurrences);
+        let mut counts: HashMap<Variable, usize> = HashMap::new();
+        for v in occurrences {
+            *counts.entry(v).or_default() += 1;
+        }
+        let names = self
+            .wildcards
+            .iter()
+            .filter(|v| counts.get(v) == Some(&1))
+            .map(|&v| (v, String::from("_")));
+        solved.display_with_names(config, names.collect())
+    }
+}
+impl<N: Name> fmt::Display for PartialSignature<N> {
+    /// The signature as written, with wildcards shown as `_`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = self.wildcards.iter().map(|&v| (v, String::from("_")));
+        let config = PrettyConfig::default();
+        let shown = self.signature.display_with_names(&config, names.collect());
+        write!(f, "{}", shown)
+    }
+}
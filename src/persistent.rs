@@ -0,0 +1,186 @@
+use im::HashMap;
+use std::borrow::Cow;
+use std::collections::HashMap as StdHashMap;
+
+use crate::theory::unify_args;
+use crate::types::RECURSION_LIMIT;
+use crate::visit::{rebuild, Rebuilder};
+use crate::{Context, Name, Type, TypeSchema, UnificationError, Variable};
+
+/// A [`Context`] backed by a persistent hash map, so that [`clone`] takes
+/// constant time and contexts derived from one another share structure.
+///
+/// Rather than changing the context, [`unify`] returns a new context with the
+/// added constraints, which suits search algorithms that keep many candidate
+/// contexts alive. Contexts convert to and from the mutable [`Context`].
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, PersistentContext};
+/// let mut ctx = PersistentContext::default();
+/// let map = ptp!(0, 1; @arrow[
+///     tp!(@arrow[tp!(0), tp!(1)]),
+///     tp!(list(tp!(0))),
+///     tp!(list(tp!(1))),
+/// ]);
+/// let t = ctx.instantiate(&map);
+/// let ret = ctx.new_variable();
+///
+/// // explore two ways of applying map, keeping both contexts
+/// let ints = ctx
+///     .unify(&t, &tp!(@arrow[tp!(@arrow[tp!(int), tp!(bool)]), tp!(list(tp!(int))), ret.clone()]))
+///     .expect("unifies");
+/// let bools = ctx
+///     .unify(&t, &tp!(@arrow[tp!(@arrow[tp!(bool), tp!(int)]), tp!(list(tp!(bool))), ret.clone()]))
+///     .expect("unifies");
+/// assert_eq!(ints.apply(&ret).to_string(), "list(bool)");
+/// assert_eq!(bools.apply(&ret).to_string(), "list(int)");
+/// assert!(ctx.is_empty());
+/// ```
+///
+/// [`Context`]: struct.Context.html
+/// [`clone`]: #impl-Clone
+/// [`unify`]: #method.unify
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PersistentContext<N: Name = &'static str> {
+    substitution: HashMap<Variable, Type<N>>,
+    next: Variable,
+}
+impl<N: Name> Default for PersistentContext<N> {
+    fn default() -> Self {
+        PersistentContext {
+            substitution: HashMap::new(),
+            next: 0,
+        }
+    }
+}
+impl<N: Name> PersistentContext<N> {
+    /// The substitution managed by the context.
+    pub fn substitution(&self) -> &HashMap<Variable, Type<N>> {
+        &self.substitution
+    }
+    /// The number of constraints in the substitution.
+    pub fn len(&self) -> usize {
+        self.substitution.len()
+    }
+    /// `true` if the substitution has any constraints, else `false`.
+    pub fn is_empty(&self) -> bool {
+        self.substitution.is_empty()
+    }
+    /// Create a new substitution for [`Type::Variable`] number `v` to the
+    /// [`Type`] `t`.
+    ///
+    /// [`Type`]: enum.Type.html
+    /// [`Type::Variable`]: enum.Type.html#variant.Variable
+    pub fn extend(&mut self, v: Variable, t: Type<N>) {
+        if v >= self.next {
+            self.next = v + 1
+        }
+        self.substitution.insert(v, t);
+    }
+    /// Create a new [`Type::Variable`] from the next unused number.
+    ///
+    /// [`Type::Variable`]: enum.Type.html#variant.Variable
+    pub fn new_variable(&mut self) -> Type<N> {
+        self.next += 1;
+        Type::Variable(self.next - 1)
+    }
+    /// Like [`TypeSchema::instantiate`], using fresh variables from this
+    /// context.
+    ///
+    /// [`TypeSchema::instantiate`]: enum.TypeSchema.html#method.instantiate
+    pub fn instantiate(&mut self, schema: &TypeSchema<N>) -> Type<N> {
+        let mut substitution = StdHashMap::new();
+        let mut schema = schema;
+        loop {
+            match *schema {
+                TypeSchema::Monotype(ref t) => return t.substitute(&substitution),
+                TypeSchema::Polytype { variable, ref body } => {
+                    substitution.insert(variable, self.new_variable());
+                    schema = body;
+                }
+            }
+        }
+    }
+    /// Like [`Type::apply`], using this context.
+    ///
+    /// [`Type::apply`]: enum.Type.html#method.apply
+    pub fn apply(&self, t: &Type<N>) -> Type<N> {
+        rebuild(
+            Cow::Borrowed(t),
+            &mut Apply(&self.substitution),
+            RECURSION_LIMIT,
+        )
+    }
+    /// A new context in which `t1` and `t2` unify, sharing structure with
+    /// this one. See [`Context::unify`].
+    ///
+    /// [`Context::unify`]: struct.Context.html#method.unify
+    pub fn unify(&self, t1: &Type<N>, t2: &Type<N>) -> Result<Self, UnificationError<N>> {
+        let mut ctx = self.clone();
+        let mut pending = vec![(t1.clone(), t2.clone())];
+        while let Some((t1, t2)) = pending.pop() {
+            match (ctx.resolve(t1), ctx.resolve(t2)) {
+                (Type::Variable(v1), Type::Variable(v2)) if v1 == v2 => (),
+                (Type::Variable(v), t) | (t, Type::Variable(v)) => {
+                    let t = ctx.apply(&t);
+                    if t.occurs(v) {
+                        return Err(UnificationError::Occurs(v));
+                    }
+                    ctx.extend(v, t);
+                }
+                (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => {
+                    if n1 != n2 {
+                        return Err(UnificationError::Failure(
+                            ctx.apply(&Type::Constructed(n1, a1)),
+                            ctx.apply(&Type::Constructed(n2, a2)),
+                        ));
+                    }
+                    unify_args(&mut ctx, n1, a1, a2, &mut pending)?;
+                }
+            }
+        }
+        Ok(ctx)
+    }
+    /// If `t` is a variable with a substitution, the substituted type.
+    pub(crate) fn resolve(&self, t: Type<N>) -> Type<N> {
+        match t {
+            Type::Variable(v) if self.substitution.contains_key(&v) => self.apply(&t),
+            t => t,
+        }
+    }
+}
+/// Applies a persistent substitution.
+struct Apply<'a, N: Name>(&'a HashMap<Variable, Type<N>>);
+impl<'a, N: Name> Rebuilder<'a, N, N> for Apply<'a, N> {
+    fn constructed(&mut self, name: &N, args: Vec<Type<N>>) -> Type<N> {
+        Type::Constructed(name.clone(), args)
+    }
+    fn variable(&mut self, v: Variable) -> Result<Type<N>, Cow<'a, Type<N>>> {
+        match self.0.get(&v) {
+            Some(tp) => Err(Cow::Borrowed(tp)),
+            None => Ok(Type::Variable(v)),
+        }
+    }
+}
+impl<N: Name> From<Context<N>> for PersistentContext<N> {
+    fn from(mut ctx: Context<N>) -> Self {
+        PersistentContext {
+            substitution: std::mem::take(&mut ctx.substitution).into_iter().collect(),
+            next: ctx.next,
+        }
+    }
+}
+impl<N: Name> From<PersistentContext<N>> for Context<N> {
+    fn from(ctx: PersistentContext<N>) -> Self {
+        let mut new = Context::default();
+        for (v, t) in ctx.substitution {
+            new.extend(v, t);
+        }
+        while new.next < ctx.next {
+            new.new_variable();
+        }
+        new
+    }
+}
@@ -0,0 +1,429 @@
+use itertools::Itertools;
+use std::collections::HashMap;
+
+use crate::types::Shown;
+use crate::{Name, Type, TypeSchema, Variable};
+
+/// How type variables are named by [`PrettyConfig`].
+///
+/// [`PrettyConfig`]: struct.PrettyConfig.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariableNaming {
+    /// `t0`, `t1`, … according to each variable's number, as with [`Display`].
+    ///
+    /// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+    Numbered,
+    /// `a`, `b`, …, `z`, `a1`, … in order of appearance.
+    Latin,
+    /// `α`, `β`, …, `ω`, `α1`, … in order of appearance.
+    Greek,
+}
+impl VariableNaming {
+    const LATIN: &'static str = "abcdefghijklmnopqrstuvwxyz";
+    const GREEK: &'static str = "αβγδεζηθικλμνξοπρστυφχψω";
+    /// The name of the `i`th variable to appear, whose number is `v`.
+    fn name(self, i: usize, v: Variable) -> String {
+        let alphabet = match self {
+            VariableNaming::Numbered => return format!("t{}", v),
+            VariableNaming::Latin => VariableNaming::LATIN,
+            VariableNaming::Greek => VariableNaming::GREEK,
+        };
+        let n = alphabet.chars().count();
+        let letter = alphabet.chars().nth(i % n).unwrap();
+        match i / n {
+            0 => letter.to_string(),
+            k => format!("{}{}", letter, k),
+        }
+    }
+    /// Names for `vars`, which are given in order of appearance.
+    fn names(self, vars: &[Variable]) -> HashMap<Variable, String> {
+        vars.iter()
+            .enumerate()
+            .map(|(i, &v)| (v, self.name(i, v)))
+            .collect()
+    }
+}
+
+/// Configures how [`Type::display_with`] and [`TypeSchema::display_with`]
+/// print types.
+///
+/// The default configuration prints exactly what [`Display`] does. With a
+/// `width`, groups which do not fit on the current line are broken across
+/// lines in the style of Wadler's [prettier printer]: arrows are broken before
+/// each `→`, and constructor arguments are placed one per line.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, PrettyConfig, VariableNaming};
+/// let t = ptp!(0, 1; @arrow[
+///     tp!(@arrow[tp!(0), tp!(1)]),
+///     tp!(dict(tp!(str), tp!(0))),
+///     tp!(dict(tp!(str), tp!(1))),
+/// ]);
+/// assert_eq!(t.display_with(&PrettyConfig::default()), t.to_string());
+///
+/// let config = PrettyConfig {
+///     ascii_arrows: true,
+///     ascii_quantifiers: true,
+///     comma_space: true,
+///     naming: VariableNaming::Latin,
+///     ..PrettyConfig::default()
+/// };
+/// assert_eq!(
+///     t.display_with(&config),
+///     "forall a b. (a -> b) -> dict(str, a) -> dict(str, b)",
+/// );
+///
+/// let config = PrettyConfig {
+///     width: Some(30),
+///     ..config
+/// };
+/// assert_eq!(
+///     t.display_with(&config),
+///     "forall a b.
+///   (a -> b)
+///   -> dict(str, a)
+///   -> dict(str, b)",
+/// );
+/// ```
+///
+/// [`Type::display_with`]: enum.Type.html#method.display_with
+/// [`TypeSchema::display_with`]: enum.TypeSchema.html#method.display_with
+/// [`Display`]: https://doc.rust-lang.org/std/fmt/trait.Display.html
+/// [prettier printer]: https://homepages.inf.ed.ac.uk/wadler/papers/prettier/prettier.pdf
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrettyConfig {
+    /// Print arrows as `->` rather than `→`.
+    pub ascii_arrows: bool,
+    /// Print quantifiers as `forall` rather than `∀`.
+    pub ascii_quantifiers: bool,
+    /// Print a space after each comma separating constructor arguments.
+    pub comma_space: bool,
+    /// The maximum line width, if lines should be broken at all.
+    pub width: Option<usize>,
+    /// The number of spaces by which broken lines are indented.
+    pub indent: usize,
+    /// How type variables are named. Unless variables are
+    /// [`VariableNaming::Numbered`], consecutive quantifiers are grouped
+    /// together (e.g. `∀a b.` rather than `∀a. ∀b.`).
+    ///
+    /// [`VariableNaming::Numbered`]: enum.VariableNaming.html#variant.Numbered
+    pub naming: VariableNaming,
+}
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        PrettyConfig {
+            ascii_arrows: false,
+            ascii_quantifiers: false,
+            comma_space: false,
+            width: None,
+            indent: 2,
+            naming: VariableNaming::Numbered,
+        }
+    }
+}
+impl PrettyConfig {
+    fn arrow(&self) -> &'static str {
+        if self.ascii_arrows {
+            "->"
+        } else {
+            "→"
+        }
+    }
+    fn quantifier(&self) -> &'static str {
+        if self.ascii_quantifiers {
+            "forall "
+        } else {
+            "∀"
+        }
+    }
+    fn render(&self, doc: &Doc) -> String {
+        doc.render(self.width.unwrap_or(usize::MAX))
+    }
+    fn type_doc<N: Name>(&self, t: &Type<N>, names: &HashMap<Variable, String>) -> Doc {
+        self.type_doc_internal(t, true, names)
+    }
+    fn type_doc_internal<N: Name>(
+        &self,
+        t: &Type<N>,
+        is_return: bool,
+        names: &HashMap<Variable, String>,
+    ) -> Doc {
+        match *t {
+            Type::Variable(v) => Doc::text(names[&v].clone()),
+            Type::Constructed(..) if t.as_row().is_some() => Doc::text(self.row_text(t, names)),
+            Type::Constructed(..) if t.as_dimension().is_some() => {
+                let factors = t.as_dimension().unwrap_or_default();
+                let show = |t: &Type<N>| self.render_flat(self.type_doc(t, names));
+                Doc::text(Shown::join(Type::dimension_show(&factors), show))
+            }
+            Type::Constructed(..) if t.as_nat().is_some() => {
+                let (terms, constant) = t.as_nat().unwrap_or_default();
+                let show = |t: &Type<N>| self.render_flat(self.type_doc(t, names));
+                Doc::text(Shown::join(Type::nat_show(&terms, constant), show))
+            }
+            Type::Constructed(ref name, ref args) if args.is_empty() => Doc::text(name.show()),
+            Type::Constructed(ref name, _) if name.is_arrow() => {
+                let mut docs = vec![];
+                let mut t = t;
+                let mut arrow = String::new();
+                while let Some((alpha, beta)) = t.as_arrow() {
+                    if !docs.is_empty() {
+                        docs.push(Doc::Line(" "));
+                        docs.push(Doc::text(arrow));
+                    }
+                    docs.push(self.type_doc_internal(alpha, false, names));
+                    arrow = match t.effects() {
+                        Some(row) if row.as_row() != Some((vec![], None)) => {
+                            format!("{}{} ", self.arrow(), self.row_text(row, names))
+                        }
+                        _ => format!("{} ", self.arrow()),
+                    };
+                    t = beta;
+                }
+                docs.push(Doc::Line(" "));
+                docs.push(Doc::text(arrow));
+                docs.push(self.type_doc_internal(t, true, names));
+                if is_return {
+                    Doc::Group(Box::new(Doc::Concat(docs)))
+                } else {
+                    Doc::Group(Box::new(Doc::Concat(vec![
+                        Doc::text("("),
+                        Doc::Nest(1, Box::new(Doc::Concat(docs))),
+                        Doc::text(")"),
+                    ])))
+                }
+            }
+            Type::Constructed(ref name, ref args) => {
+                let sep = if self.comma_space { " " } else { "" };
+                let mut docs = vec![Doc::Line("")];
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        docs.push(Doc::text(","));
+                        docs.push(Doc::Line(sep));
+                    }
+                    docs.push(self.type_doc_internal(arg, true, names));
+                }
+                let (open, close) = if args.len() == 1 && N::list().as_ref() == Some(name) {
+                    (String::from("["), "]")
+                } else if args.len() > 1 && N::tuple().as_ref() == Some(name) {
+                    (String::from("("), ")")
+                } else {
+                    (format!("{}(", name.show()), ")")
+                };
+                Doc::Group(Box::new(Doc::Concat(vec![
+                    Doc::text(open),
+                    Doc::Nest(self.indent, Box::new(Doc::Concat(docs))),
+                    Doc::Line(""),
+                    Doc::text(close),
+                ])))
+            }
+        }
+    }
+    /// An effect row (e.g. `{io, rand | e}`), which is never broken.
+    fn row_text<N: Name>(&self, row: &Type<N>, names: &HashMap<Variable, String>) -> String {
+        let (labels, tail) = match row.as_row() {
+            Some(row) => row,
+            None => (vec![], Some(row)),
+        };
+        let sep = if self.comma_space { ", " } else { "," };
+        let labels = labels
+            .into_iter()
+            .map(|t| self.render_flat(self.type_doc(t, names)))
+            .join(sep);
+        match tail {
+            None => format!("{{{}}}", labels),
+            Some(tail) => {
+                let tail = self.render_flat(self.type_doc(tail, names));
+                if labels.is_empty() {
+                    format!("{{{}}}", tail)
+                } else {
+                    format!("{{{} | {}}}", labels, tail)
+                }
+            }
+        }
+    }
+    fn render_flat(&self, doc: Doc) -> String {
+        doc.render(usize::MAX)
+    }
+    fn schema_doc<N: Name>(&self, t: &TypeSchema<N>) -> Doc {
+        let bound = t.bound_vars();
+        let mut body = t;
+        while let TypeSchema::Polytype { body: ref b, .. } = *body {
+            body = b;
+        }
+        let body = match *body {
+            TypeSchema::Monotype(ref t) => t,
+            TypeSchema::Polytype { .. } => unreachable!(),
+        };
+        let mut vars = bound.clone();
+        vars.extend(
+            body.vars_in_order()
+                .into_iter()
+                .filter(|v| !bound.contains(v)),
+        );
+        let names = self.naming.names(&vars);
+        let body = self.type_doc(body, &names);
+        if bound.is_empty() {
+            return body;
+        }
+        let quantifiers = if self.naming == VariableNaming::Numbered {
+            bound
+                .iter()
+                .map(|v| format!("{}{}.", self.quantifier(), names[v]))
+                .join(" ")
+        } else {
+            format!(
+                "{}{}.",
+                self.quantifier(),
+                bound.iter().map(|v| &names[v]).join(" ")
+            )
+        };
+        Doc::Group(Box::new(Doc::Concat(vec![
+            Doc::text(quantifiers),
+            Doc::Nest(
+                self.indent,
+                Box::new(Doc::Concat(vec![Doc::Line(" "), body])),
+            ),
+        ])))
+    }
+}
+
+/// A document to be laid out within some width.
+enum Doc {
+    Text(String),
+    /// A line break, or the given text if the enclosing group fits flat.
+    Line(&'static str),
+    Concat(Vec<Doc>),
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+}
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Flat,
+    Break,
+}
+impl Doc {
+    fn text<S: Into<String>>(s: S) -> Doc {
+        Doc::Text(s.into())
+    }
+    fn render(&self, width: usize) -> String {
+        let mut out = String::new();
+        let mut column = 0;
+        let mut stack = vec![(0, Mode::Break, self)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match *doc {
+                Doc::Text(ref s) => {
+                    out.push_str(s);
+                    column += s.chars().count();
+                }
+                Doc::Line(flat) if mode == Mode::Flat => {
+                    out.push_str(flat);
+                    column += flat.chars().count();
+                }
+                Doc::Line(_) => {
+                    out.push('\n');
+                    out.push_str(&" ".repeat(indent));
+                    column = indent;
+                }
+                Doc::Concat(ref docs) => {
+                    stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc)));
+                }
+                Doc::Nest(i, ref doc) => stack.push((indent + i, mode, doc)),
+                Doc::Group(ref doc) => {
+                    let mode = if mode == Mode::Flat
+                        || Doc::fits(width.saturating_sub(column), doc, &stack)
+                    {
+                        Mode::Flat
+                    } else {
+                        Mode::Break
+                    };
+                    stack.push((indent, mode, doc));
+                }
+            }
+        }
+        out
+    }
+    /// Whether `doc`, laid out flat, and whatever follows it up to the next
+    /// line break fit within `remaining` columns.
+    fn fits(remaining: usize, doc: &Doc, rest: &[(usize, Mode, &Doc)]) -> bool {
+        let mut remaining = remaining.min(isize::MAX as usize) as isize;
+        let mut stack = vec![(Mode::Flat, doc)];
+        let mut rest = rest.iter().rev();
+        loop {
+            let (mode, doc) = match stack.pop() {
+                Some(next) => next,
+                None => match rest.next() {
+                    Some(&(_, mode, doc)) => (mode, doc),
+                    None => return true,
+                },
+            };
+            match *doc {
+                Doc::Text(ref s) => remaining -= s.chars().count() as isize,
+                Doc::Line(flat) if mode == Mode::Flat => remaining -= flat.chars().count() as isize,
+                Doc::Line(_) => return true,
+                Doc::Concat(ref docs) => stack.extend(docs.iter().rev().map(|doc| (mode, doc))),
+                Doc::Nest(_, ref doc) | Doc::Group(ref doc) => stack.push((mode, doc)),
+            }
+            if remaining < 0 {
+                return false;
+            }
+        }
+    }
+}
+
+impl<N: Name> TypeSchema<N> {
+    /// Show the [`TypeSchema`] according to `config`. Bound variables are
+    /// named first, followed by free variables in order of appearance, so
+    /// every variable keeps one name throughout the schema. See
+    /// [`PrettyConfig`] for an example.
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`PrettyConfig`]: struct.PrettyConfig.html
+    pub fn display_with(&self, config: &PrettyConfig) -> String {
+        config.render(&config.schema_doc(self))
+    }
+}
+impl<N: Name> Type<N> {
+    /// Show the [`Type`] according to `config`. Variables are named in order
+    /// of appearance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, PrettyConfig};
+    /// let t = tp!(@arrow[tp!(int), tp!(dict(tp!(str), tp!(list(tp!(int)))))]);
+    /// let config = PrettyConfig {
+    ///     width: Some(12),
+    ///     ..PrettyConfig::default()
+    /// };
+    /// assert_eq!(
+    ///     t.display_with(&config),
+    ///     "int
+    /// → dict(
+    ///   str,
+    ///   list(int)
+    /// )",
+    /// );
+    /// ```
+    ///
+    /// [`Type`]: enum.Type.html
+    pub fn display_with(&self, config: &PrettyConfig) -> String {
+        self.display_with_names(config, HashMap::new())
+    }
+    /// Like [`display_with`], but with the variables in `names` named as
+    /// given, and the others named in order of appearance.
+    ///
+    /// [`display_with`]: #method.display_with
+    pub(crate) fn display_with_names(
+        &self,
+        config: &PrettyConfig,
+        mut names: HashMap<Variable, String>,
+    ) -> String {
+        let mut vars = self.vars_in_order();
+        vars.retain(|v| !names.contains_key(v));
+        names.extend(config.naming.names(&vars));
+        config.render(&config.type_doc(self, &names))
+    }
+}
@@ -0,0 +1,214 @@
+use indexmap::IndexMap;
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::{Context, Name, Type, TypeSchema, UnificationError, Variable};
+
+/// A finite mapping from [`Variable`]s to [`Type`]s, applied simultaneously.
+///
+/// Unlike a [`Context`], a substitution is applied in a single pass: the
+/// types it substitutes are not themselves substituted. A substitution is
+/// *idempotent* when no variable of its domain occurs in its range, in which
+/// case applying it once is the same as applying it repeatedly. A
+/// substitution taken from a [`Context`] is always idempotent.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{tp, Substitution};
+/// let s1: Substitution = vec![(0, tp!(list(tp!(1))))].into_iter().collect();
+/// let s2: Substitution = vec![(1, tp!(int))].into_iter().collect();
+///
+/// let t = tp!(@arrow[tp!(0), tp!(1)]);
+/// assert_eq!(s1.apply(&t).to_string(), "list(t1) → t1");
+///
+/// // s1, then s2
+/// let s = s1.compose(&s2);
+/// assert_eq!(s.apply(&t).to_string(), "list(int) → int");
+/// assert_eq!(s.domain(), vec![0, 1]);
+/// assert!(s.range_vars().is_empty());
+/// ```
+///
+/// [`Variable`]: type.Variable.html
+/// [`Type`]: enum.Type.html
+/// [`Context`]: struct.Context.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution<N: Name = &'static str> {
+    map: IndexMap<Variable, Type<N>>,
+}
+impl<N: Name> Default for Substitution<N> {
+    fn default() -> Self {
+        Substitution {
+            map: IndexMap::new(),
+        }
+    }
+}
+impl<N: Name> Substitution<N> {
+    /// Create an empty substitution.
+    pub fn new() -> Self {
+        Substitution::default()
+    }
+    /// The number of variables in the domain.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+    /// Whether the substitution is the identity.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+    /// The type substituted for `v`, if any.
+    pub fn get(&self, v: Variable) -> Option<&Type<N>> {
+        self.map.get(&v)
+    }
+    /// Substitute `t` for `v`, returning the type previously substituted.
+    pub fn insert(&mut self, v: Variable, t: Type<N>) -> Option<Type<N>> {
+        self.map.insert(v, t)
+    }
+    /// Iterate over the variables and the types substituted for them, in the
+    /// order they were inserted.
+    pub fn iter(&self) -> impl Iterator<Item = (Variable, &Type<N>)> {
+        self.map.iter().map(|(&v, t)| (v, t))
+    }
+    /// The variables which are substituted, in the order they were inserted.
+    pub fn domain(&self) -> Vec<Variable> {
+        self.map.keys().cloned().collect()
+    }
+    /// The variables occurring in the substituted types, sorted.
+    pub fn range_vars(&self) -> Vec<Variable> {
+        let mut vars: Vec<_> = self.map.values().flat_map(Type::vars).collect();
+        vars.sort_unstable();
+        vars.dedup();
+        vars
+    }
+    /// Whether no variable of the domain occurs in the range.
+    pub fn is_idempotent(&self) -> bool {
+        self.range_vars().iter().all(|v| !self.map.contains_key(v))
+    }
+    /// Substitute simultaneously for the variables of `t`.
+    pub fn apply(&self, t: &Type<N>) -> Type<N> {
+        t.map_vars(|v| match self.map.get(&v) {
+            Some(t) => t.clone(),
+            None => Type::Variable(v),
+        })
+    }
+    /// Substitute simultaneously for the free variables of `schema`, as in
+    /// [`TypeSchema::substitute`]. Bound variables shadow the substitution,
+    /// and quantifiers which would capture a substituted variable are renamed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp, Substitution};
+    /// let s: Substitution = vec![(0, tp!(int)), (1, tp!(bool))].into_iter().collect();
+    /// let schema = ptp!(0; @arrow[tp!(0), tp!(1)]);
+    /// assert_eq!(s.apply_to_schema(&schema).to_string(), "∀t0. t0 → bool");
+    /// ```
+    ///
+    /// [`TypeSchema::substitute`]: enum.TypeSchema.html#method.substitute
+    pub fn apply_to_schema(&self, schema: &TypeSchema<N>) -> TypeSchema<N> {
+        schema.substitute_with(|v| self.map.get(&v))
+    }
+    /// The substitution which applies `self` and then `other`, i.e. for which
+    /// `self.compose(other).apply(t) == other.apply(&self.apply(t))`.
+    pub fn compose(&self, other: &Substitution<N>) -> Substitution<N> {
+        let mut map: IndexMap<_, _> = self.map.iter().map(|(&v, t)| (v, other.apply(t))).collect();
+        for (&v, t) in &other.map {
+            map.entry(v).or_insert_with(|| t.clone());
+        }
+        let mut composed = Substitution { map };
+        composed.remove_trivial();
+        composed
+    }
+    /// The substitution acting only on the variables in `vars`.
+    pub fn restrict(&self, vars: &[Variable]) -> Substitution<N> {
+        Substitution {
+            map: self
+                .map
+                .iter()
+                .filter(|(v, _)| vars.contains(v))
+                .map(|(&v, t)| (v, t.clone()))
+                .collect(),
+        }
+    }
+    /// Make the substitution idempotent. Each `v ↦ t` is treated as the
+    /// equation `v = t`, as in a [`Context`], and the substitution is replaced
+    /// by the most general solution of the equations. An idempotent
+    /// substitution is left as it is, except that variables mapped to
+    /// themselves are dropped. Fails if the equations have no solution.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Substitution, UnificationError};
+    /// let mut s: Substitution = vec![(0, tp!(list(tp!(1)))), (1, tp!(int))]
+    ///     .into_iter()
+    ///     .collect();
+    /// assert!(!s.is_idempotent());
+    /// s.normalize().expect("no cycles");
+    /// assert!(s.is_idempotent());
+    /// assert_eq!(s.get(0), Some(&tp!(list(tp!(int)))));
+    ///
+    /// let mut s: Substitution = vec![(0, tp!(list(tp!(1)))), (1, tp!(0))]
+    ///     .into_iter()
+    ///     .collect();
+    /// assert_eq!(s.normalize(), Err(UnificationError::Occurs(1)));
+    /// ```
+    ///
+    /// [`Context`]: struct.Context.html
+    pub fn normalize(&mut self) -> Result<(), UnificationError<N>> {
+        let mut ctx = Context::default();
+        for (&v, t) in &self.map {
+            ctx.unify(&Type::Variable(v), t)?;
+        }
+        *self = Substitution::from(ctx);
+        Ok(())
+    }
+    fn remove_trivial(&mut self) {
+        self.map.retain(|&v, t| *t != Type::Variable(v));
+    }
+}
+impl<N: Name> FromIterator<(Variable, Type<N>)> for Substitution<N> {
+    fn from_iter<I: IntoIterator<Item = (Variable, Type<N>)>>(iter: I) -> Self {
+        Substitution {
+            map: iter.into_iter().collect(),
+        }
+    }
+}
+impl<N: Name> From<HashMap<Variable, Type<N>>> for Substitution<N> {
+    fn from(map: HashMap<Variable, Type<N>>) -> Self {
+        map.into_iter().collect()
+    }
+}
+impl<N: Name> From<Substitution<N>> for HashMap<Variable, Type<N>> {
+    fn from(substitution: Substitution<N>) -> Self {
+        substitution.map.into_iter().collect()
+    }
+}
+impl<'a, N: Name> From<&'a Context<N>> for Substitution<N> {
+    /// The idempotent substitution equivalent to the context.
+    fn from(ctx: &'a Context<N>) -> Self {
+        ctx.substitution()
+            .keys()
+            .map(|&v| (v, Type::Variable(v).apply(ctx)))
+            .collect()
+    }
+}
+impl<N: Name> From<Context<N>> for Substitution<N> {
+    /// The idempotent substitution equivalent to the context.
+    fn from(ctx: Context<N>) -> Self {
+        Substitution::from(&ctx)
+    }
+}
+impl<N: Name> fmt::Display for Substitution<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (v, t)) in self.map.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "t{} ↦ {}", v, t)?;
+        }
+        write!(f, "}}")
+    }
+}
@@ -0,0 +1,539 @@
+use std::collections::HashMap;
+
+use crate::{Context, Name, Type, TypeSchema, UnificationError, Variable};
+
+/// How an argument of a constructor varies with the constructed type under
+/// subtyping. See [`SubtypeLattice::set_variance`].
+///
+/// [`SubtypeLattice::set_variance`]: struct.SubtypeLattice.html#method.set_variance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Variance {
+    /// `α <: β` implies `F(α) <: F(β)`, as for the result of an arrow.
+    Covariant,
+    /// `α <: β` implies `F(β) <: F(α)`, as for the argument of an arrow.
+    Contravariant,
+    /// `F(α) <: F(β)` only if `α` and `β` are mutual subtypes.
+    Invariant,
+}
+
+/// The subtyping constraints on a type variable: every type in `lower` is a
+/// subtype of it, and it is a subtype of every type in `upper`.
+///
+/// See [`Context::constrain_subtype`].
+///
+/// [`Context::constrain_subtype`]: struct.Context.html#method.constrain_subtype
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bounds<N: Name = &'static str> {
+    /// Subtypes of the variable.
+    pub lower: Vec<Type<N>>,
+    /// Supertypes of the variable.
+    pub upper: Vec<Type<N>>,
+}
+impl<N: Name> Default for Bounds<N> {
+    fn default() -> Self {
+        Bounds {
+            lower: vec![],
+            upper: vec![],
+        }
+    }
+}
+
+/// The subtyping relation between constructors, as declared by the user.
+///
+/// A constructor is a subtype of itself and of everything it is declared,
+/// directly or transitively, to be a subtype of. Each declaration also says
+/// which argument of the subtype stands for each argument of the supertype,
+/// so a supertype may take fewer arguments: this models width subtyping of
+/// records, with fields as arguments. Arguments are compared according to
+/// their [`Variance`], which is covariant unless set otherwise, except for
+/// the argument of an arrow.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{tp, Context, SubtypeLattice};
+/// let mut lattice = SubtypeLattice::new();
+/// lattice.declare("int", "real");
+/// lattice.declare("point3", "point2"); // point3(x, y, z) <: point2(x, y)
+///
+/// // the type of λp. norm(p), where norm: point2(real, real) → real
+/// let mut ctx = Context::default();
+/// let p = ctx.new_variable();
+/// let r = ctx.new_variable();
+/// let real2 = tp!(point2(tp!(real), tp!(real)));
+/// ctx.constrain_subtype(&lattice, &p, &real2).expect("constrains");
+/// ctx.constrain_subtype(&lattice, &tp!(real), &r).expect("constrains");
+///
+/// let t = tp!(@arrow[p.clone(), r]);
+/// assert_eq!(ctx.simplify_subtype(&lattice, &t).to_string(), "point2(real,real) → real");
+///
+/// // p may be a point3 of ints, but not a bool
+/// let int3 = tp!(point3(tp!(int), tp!(int), tp!(int)));
+/// assert!(ctx.constrain_subtype(&lattice, &int3, &p).is_ok());
+/// assert!(ctx.constrain_subtype(&lattice, &tp!(bool), &p).is_err());
+/// ```
+///
+/// [`Variance`]: enum.Variance.html
+#[derive(Debug, Clone)]
+pub struct SubtypeLattice<N: Name = &'static str> {
+    /// Declared subtypes, supertypes, and for each argument of the supertype
+    /// the argument of the subtype, if not positional.
+    edges: Vec<(N, N, Option<Vec<usize>>)>,
+    variances: Vec<(N, Vec<Variance>)>,
+}
+impl<N: Name> Default for SubtypeLattice<N> {
+    fn default() -> Self {
+        SubtypeLattice {
+            edges: vec![],
+            variances: vec![],
+        }
+    }
+}
+impl<N: Name> SubtypeLattice<N> {
+    /// Create a lattice in which constructors are subtypes only of
+    /// themselves.
+    pub fn new() -> Self {
+        SubtypeLattice::default()
+    }
+    /// Declare `sub` a subtype of `sup`, where each argument of `sup` is the
+    /// argument of `sub` in the same position. `sup` may take fewer
+    /// arguments.
+    pub fn declare(&mut self, sub: N, sup: N) {
+        self.edges.push((sub, sup, None))
+    }
+    /// Declare `sub` a subtype of `sup`, where argument `i` of `sup` is
+    /// argument `projection[i]` of `sub`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Context, SubtypeLattice};
+    /// let mut lattice = SubtypeLattice::new();
+    /// // labelled(x, y, label) <: pair(y, x)
+    /// lattice.declare_projection("labelled", "pair", vec![1, 0]);
+    ///
+    /// let mut ctx = Context::default();
+    /// let labelled = tp!(labelled(tp!(int), tp!(bool), tp!(str)));
+    /// assert!(ctx.constrain_subtype(&lattice, &labelled, &tp!(pair(tp!(bool), tp!(int)))).is_ok());
+    /// assert!(ctx.constrain_subtype(&lattice, &labelled, &tp!(pair(tp!(int), tp!(bool)))).is_err());
+    /// ```
+    pub fn declare_projection(&mut self, sub: N, sup: N, projection: Vec<usize>) {
+        self.edges.push((sub, sup, Some(projection)))
+    }
+    /// Set the variance of each argument of `name`.
+    pub fn set_variance(&mut self, name: N, variances: Vec<Variance>) {
+        self.variances.retain(|(n, _)| *n != name);
+        self.variances.push((name, variances))
+    }
+    /// The variance of argument `i` of `name`.
+    pub fn variance(&self, name: &N, i: usize) -> Variance {
+        match self.variances.iter().find(|(n, _)| n == name) {
+            Some((_, variances)) if i < variances.len() => variances[i],
+            _ if *name == N::arrow() && i == 0 => Variance::Contravariant,
+            _ => Variance::Covariant,
+        }
+    }
+    /// Whether `sub` is declared, directly or transitively, a subtype of
+    /// `sup`, or is `sup`.
+    pub fn is_subconstructor(&self, sub: &N, sup: &N) -> bool {
+        self.projection(sub, sup).is_some()
+    }
+    /// For each argument of `sup`, the argument of `sub` it stands for, or
+    /// `Some(None)` if arguments are positional. `None` if `sub` is not a
+    /// subtype of `sup`.
+    fn projection(&self, sub: &N, sup: &N) -> Option<Option<Vec<usize>>> {
+        let mut reached: Vec<(&N, Option<Vec<usize>>)> = vec![(sub, None)];
+        let mut i = 0;
+        while i < reached.len() {
+            if reached[i].0 == sup {
+                return Some(reached[i].1.clone());
+            }
+            for (from, to, step) in &self.edges {
+                if from != reached[i].0 || reached.iter().any(|(n, _)| *n == to) {
+                    continue;
+                }
+                let projection = match (&reached[i].1, step) {
+                    (None, step) => step.clone(),
+                    (Some(p), None) => Some(p.clone()),
+                    (Some(p), Some(step)) => {
+                        match step.iter().map(|&j| p.get(j).cloned()).collect() {
+                            Some(p) => Some(p),
+                            None => continue,
+                        }
+                    }
+                };
+                reached.push((to, projection));
+            }
+            i += 1;
+        }
+        None
+    }
+    /// `name` and every constructor in a declaration.
+    fn related<'a>(&'a self, name: &'a N) -> Vec<&'a N> {
+        let mut names = vec![name];
+        for (sub, sup, _) in &self.edges {
+            for n in &[sub, sup] {
+                if !names.contains(n) {
+                    names.push(n)
+                }
+            }
+        }
+        names
+    }
+    /// The least type of which `t1` and `t2` are both subtypes, if there is
+    /// one without union types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, SubtypeLattice};
+    /// let mut lattice = SubtypeLattice::new();
+    /// lattice.declare("int", "real");
+    /// let t1 = tp!(@arrow[tp!(int), tp!(int)]);
+    /// let t2 = tp!(@arrow[tp!(real), tp!(real)]);
+    /// assert_eq!(lattice.join(&t1, &t2), Some(tp!(@arrow[tp!(int), tp!(real)])));
+    /// assert_eq!(lattice.meet(&t1, &t2), Some(tp!(@arrow[tp!(real), tp!(int)])));
+    /// assert_eq!(lattice.join(&tp!(int), &tp!(bool)), None);
+    /// ```
+    pub fn join(&self, t1: &Type<N>, t2: &Type<N>) -> Option<Type<N>> {
+        self.bound(t1, t2, true)
+    }
+    /// The greatest type which is a subtype of both `t1` and `t2`, if there
+    /// is one without intersection types. See [`join`].
+    ///
+    /// [`join`]: #method.join
+    pub fn meet(&self, t1: &Type<N>, t2: &Type<N>) -> Option<Type<N>> {
+        self.bound(t1, t2, false)
+    }
+    fn bound(&self, t1: &Type<N>, t2: &Type<N>, upper: bool) -> Option<Type<N>> {
+        if t1 == t2 {
+            return Some(t1.clone());
+        }
+        let (n1, a1, n2, a2) = match (t1, t2) {
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) => (n1, a1, n2, a2),
+            _ => return None,
+        };
+        let below = |sub: &N, sup: &N| {
+            if upper {
+                self.is_subconstructor(sub, sup)
+            } else {
+                self.is_subconstructor(sup, sub)
+            }
+        };
+        let candidates: Vec<_> = self
+            .related(n1)
+            .into_iter()
+            .filter(|c| below(n1, c) && below(n2, c))
+            .collect();
+        let name = *candidates
+            .iter()
+            .find(|c| candidates.iter().all(|d| below(c, d)))?;
+        let args = if upper {
+            // every argument of the join is an argument of each side
+            let p1 = self.projection(n1, name)?;
+            let p2 = self.projection(n2, name)?;
+            let arity = match (&p1, &p2) {
+                (Some(p), _) | (None, Some(p)) => p.len(),
+                (None, None) => a1.len().min(a2.len()),
+            };
+            let mut args = Vec::with_capacity(arity);
+            for i in 0..arity {
+                let x1 = a1.get(p1.as_ref().map_or(i, |p| p[i]))?;
+                let x2 = a2.get(p2.as_ref().map_or(i, |p| p[i]))?;
+                args.push(self.bound_arg(name, i, x1, x2, true)?);
+            }
+            args
+        } else {
+            // every argument of each side is an argument of the meet, and
+            // every argument of the meet must be one of them
+            let mut args: Vec<Option<Type<N>>> = vec![];
+            for (sup, sup_args) in &[(n1, a1), (n2, a2)] {
+                let p = self.projection(name, sup)?;
+                for (i, x) in sup_args.iter().enumerate() {
+                    let j = p.as_ref().map_or(Some(i), |p| p.get(i).cloned())?;
+                    if args.len() <= j {
+                        args.resize(j + 1, None);
+                    }
+                    args[j] = Some(match args[j].take() {
+                        None => x.clone(),
+                        Some(y) => self.bound_arg(name, j, &y, x, false)?,
+                    });
+                }
+            }
+            args.into_iter().collect::<Option<_>>()?
+        };
+        Some(Type::Constructed(name.clone(), args))
+    }
+    fn bound_arg(
+        &self,
+        name: &N,
+        i: usize,
+        x1: &Type<N>,
+        x2: &Type<N>,
+        upper: bool,
+    ) -> Option<Type<N>> {
+        match self.variance(name, i) {
+            Variance::Covariant => self.bound(x1, x2, upper),
+            Variance::Contravariant => self.bound(x1, x2, !upper),
+            Variance::Invariant if x1 == x2 => Some(x1.clone()),
+            Variance::Invariant => None,
+        }
+    }
+}
+
+impl<N: Name> Context<N> {
+    /// The subtyping constraints on `v`, if it has any.
+    pub fn bounds(&self, v: Variable) -> Option<&Bounds<N>> {
+        self.bounds.get(&v)
+    }
+    /// Constrain `t1` to be a subtype of `t2`, according to `lattice`.
+    ///
+    /// Constraints on variables are recorded as [`Bounds`] by biunification:
+    /// a new upper bound on a variable is constrained to be a supertype of
+    /// each of its lower bounds, and vice versa, so the constraints are
+    /// always consistent with one another. Variables are first resolved using
+    /// the context's substitution, but bounds are not checked by
+    /// [`unify`], and are not carried over by [`merge`] or [`solved`]. If the
+    /// constraints cannot be satisfied, the context is left unaffected and
+    /// [`UnificationError::Failure`] gives the types which are not subtypes.
+    ///
+    /// See [`SubtypeLattice`] for an example.
+    ///
+    /// [`Bounds`]: struct.Bounds.html
+    /// [`unify`]: #method.unify
+    /// [`merge`]: #method.merge
+    /// [`solved`]: #method.solved
+    /// [`UnificationError::Failure`]: enum.UnificationError.html#variant.Failure
+    /// [`SubtypeLattice`]: struct.SubtypeLattice.html
+    pub fn constrain_subtype(
+        &mut self,
+        lattice: &SubtypeLattice<N>,
+        t1: &Type<N>,
+        t2: &Type<N>,
+    ) -> Result<(), UnificationError<N>> {
+        let saved = self.bounds.clone();
+        let result = self.constrain_subtype_internal(lattice, t1.clone(), t2.clone());
+        if result.is_err() {
+            self.bounds = saved;
+        }
+        result
+    }
+    fn constrain_subtype_internal(
+        &mut self,
+        lattice: &SubtypeLattice<N>,
+        t1: Type<N>,
+        t2: Type<N>,
+    ) -> Result<(), UnificationError<N>> {
+        // every constraint is between parts of types already seen, so
+        // skipping those already handled ensures termination.
+        let mut seen: Vec<(Type<N>, Type<N>)> = vec![];
+        let mut pending = vec![(t1, t2)];
+        while let Some((lhs, rhs)) = pending.pop() {
+            let (lhs, rhs) = (self.resolve(lhs), self.resolve(rhs));
+            if lhs == rhs || seen.iter().any(|(l, r)| *l == lhs && *r == rhs) {
+                continue;
+            }
+            seen.push((lhs.clone(), rhs.clone()));
+            match (lhs, rhs) {
+                (Type::Variable(v), Type::Variable(w)) => {
+                    // recorded on both sides, so each variable's bounds are
+                    // complete when it is simplified on its own
+                    let bounds = self.bounds.entry(v).or_default();
+                    pending.extend(
+                        bounds
+                            .lower
+                            .iter()
+                            .map(|lb| (lb.clone(), Type::Variable(w))),
+                    );
+                    bounds.upper.push(Type::Variable(w));
+                    let bounds = self.bounds.entry(w).or_default();
+                    pending.extend(
+                        bounds
+                            .upper
+                            .iter()
+                            .map(|ub| (Type::Variable(v), ub.clone())),
+                    );
+                    bounds.lower.push(Type::Variable(v));
+                }
+                (Type::Variable(v), rhs) => {
+                    let rhs = rhs.apply(self);
+                    if rhs.occurs(v) {
+                        return Err(UnificationError::Occurs(v));
+                    }
+                    let bounds = self.bounds.entry(v).or_default();
+                    pending.extend(bounds.lower.iter().map(|lb| (lb.clone(), rhs.clone())));
+                    bounds.upper.push(rhs);
+                }
+                (lhs, Type::Variable(v)) => {
+                    let lhs = lhs.apply(self);
+                    if lhs.occurs(v) {
+                        return Err(UnificationError::Occurs(v));
+                    }
+                    let bounds = self.bounds.entry(v).or_default();
+                    pending.extend(bounds.upper.iter().map(|ub| (lhs.clone(), ub.clone())));
+                    bounds.lower.push(lhs);
+                }
+                (Type::Constructed(ref n1, ref a1), Type::Constructed(ref n2, ref a2)) => {
+                    let projection = match lattice.projection(n1, n2) {
+                        Some(Some(p)) if a2.len() <= p.len() && p.iter().all(|&j| j < a1.len()) => {
+                            p
+                        }
+                        Some(None) if a2.len() <= a1.len() => (0..a2.len()).collect(),
+                        _ => {
+                            let (lhs, rhs) = seen.last().unwrap();
+                            return Err(UnificationError::Failure(
+                                lhs.apply(self),
+                                rhs.apply(self),
+                            ));
+                        }
+                    };
+                    for (i, x2) in a2.iter().enumerate().rev() {
+                        let x1 = &a1[projection[i]];
+                        match lattice.variance(n2, i) {
+                            Variance::Covariant => pending.push((x1.clone(), x2.clone())),
+                            Variance::Contravariant => pending.push((x2.clone(), x1.clone())),
+                            Variance::Invariant => {
+                                pending.push((x1.clone(), x2.clone()));
+                                pending.push((x2.clone(), x1.clone()));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+    /// A [`TypeSchema`] for `t`, simplified for display using the bounds of
+    /// its variables.
+    ///
+    /// A variable which occurs only positively (e.g. in the result of an
+    /// arrow) is replaced by the [`join`] of its lower bounds, and one which
+    /// occurs only negatively by the [`meet`] of its upper bounds. Variables
+    /// whose bounds are not expressible without union or intersection types,
+    /// or which occur both ways, are left in place and quantified; their
+    /// bounds remain available from [`bounds`].
+    ///
+    /// See [`SubtypeLattice`] for an example.
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`join`]: struct.SubtypeLattice.html#method.join
+    /// [`meet`]: struct.SubtypeLattice.html#method.meet
+    /// [`bounds`]: #method.bounds
+    /// [`SubtypeLattice`]: struct.SubtypeLattice.html
+    pub fn simplify_subtype(&self, lattice: &SubtypeLattice<N>, t: &Type<N>) -> TypeSchema<N> {
+        let t = t.apply(self);
+        let (positive, negative) = polarities(lattice, &t);
+        let substitution: HashMap<_, _> = t
+            .vars()
+            .into_iter()
+            .filter(|v| positive.contains(v) != negative.contains(v))
+            .filter_map(|v| {
+                let coalesced = self.coalesce(lattice, v, positive.contains(&v), &mut vec![]);
+                coalesced.map(|t| (v, t))
+            })
+            .collect();
+        let body = t.substitute(&substitution);
+        body.vars()
+            .into_iter()
+            .rev()
+            .fold(TypeSchema::Monotype(body), |body, variable| {
+                TypeSchema::Polytype {
+                    variable,
+                    body: Box::new(body),
+                }
+            })
+    }
+    /// The join of the lower bounds of `v`, if `positive`, or the meet of
+    /// its upper bounds, with their variables likewise coalesced where
+    /// possible. `None` if there are no such bounds, they have no join
+    /// (meet), or it is just a variable.
+    fn coalesce(
+        &self,
+        lattice: &SubtypeLattice<N>,
+        v: Variable,
+        positive: bool,
+        visiting: &mut Vec<Variable>,
+    ) -> Option<Type<N>> {
+        if visiting.contains(&v) {
+            return None;
+        }
+        let bounds = self.bounds.get(&v)?;
+        let bounds = if positive {
+            &bounds.lower
+        } else {
+            &bounds.upper
+        };
+        visiting.push(v);
+        let mut coalesced: Option<Type<N>> = None;
+        for bound in bounds {
+            let bound = self.coalesce_type(lattice, &bound.apply(self), positive, visiting);
+            coalesced = match coalesced {
+                None => Some(bound),
+                Some(t) if positive => lattice.join(&t, &bound),
+                Some(t) => lattice.meet(&t, &bound),
+            };
+            if coalesced.is_none() {
+                break;
+            }
+        }
+        visiting.pop();
+        match coalesced {
+            Some(Type::Variable(_)) => None,
+            coalesced => coalesced,
+        }
+    }
+    /// Coalesce each variable of `t` where possible, as it occurs in a
+    /// position of the given polarity.
+    fn coalesce_type(
+        &self,
+        lattice: &SubtypeLattice<N>,
+        t: &Type<N>,
+        positive: bool,
+        visiting: &mut Vec<Variable>,
+    ) -> Type<N> {
+        match *t {
+            Type::Variable(v) => self
+                .coalesce(lattice, v, positive, visiting)
+                .unwrap_or_else(|| t.clone()),
+            Type::Constructed(ref name, ref args) => {
+                let args = args
+                    .iter()
+                    .enumerate()
+                    .map(|(i, arg)| match lattice.variance(name, i) {
+                        Variance::Covariant => self.coalesce_type(lattice, arg, positive, visiting),
+                        Variance::Contravariant => {
+                            self.coalesce_type(lattice, arg, !positive, visiting)
+                        }
+                        Variance::Invariant => arg.clone(),
+                    })
+                    .collect();
+                Type::Constructed(name.clone(), args)
+            }
+        }
+    }
+}
+
+/// The variables occurring in positive and in negative positions of `t`.
+fn polarities<N: Name>(lattice: &SubtypeLattice<N>, t: &Type<N>) -> (Vec<Variable>, Vec<Variable>) {
+    let (mut positive, mut negative) = (vec![], vec![]);
+    let mut stack = vec![(t, true)];
+    while let Some((t, polarity)) = stack.pop() {
+        match *t {
+            Type::Variable(v) if polarity => positive.push(v),
+            Type::Variable(v) => negative.push(v),
+            Type::Constructed(ref name, ref args) => {
+                for (i, arg) in args.iter().enumerate() {
+                    match lattice.variance(name, i) {
+                        Variance::Covariant => stack.push((arg, polarity)),
+                        Variance::Contravariant => stack.push((arg, !polarity)),
+                        Variance::Invariant => {
+                            stack.push((arg, true));
+                            stack.push((arg, false));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    (positive, negative)
+}
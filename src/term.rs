@@ -0,0 +1,93 @@
+use std::fmt;
+
+/// A term of the simply typed lambda calculus over a library of primitives.
+///
+/// Primitives are referred to by their index in the library, and variables by
+/// their [de Bruijn index], so `Variable(0)` is bound by the innermost
+/// enclosing `Abstraction`.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::Term;
+/// // λ. 0 (1 0), where 0 and 1 index primitives
+/// let t = Term::Abstraction(Box::new(Term::Application(
+///     Box::new(Term::Primitive(0)),
+///     Box::new(Term::Application(
+///         Box::new(Term::Primitive(1)),
+///         Box::new(Term::Variable(0)),
+///     )),
+/// )));
+/// assert_eq!(t.to_string(), "(λ (#0 (#1 $0)))");
+/// assert_eq!(t.size(), 3);
+/// ```
+///
+/// [de Bruijn index]: https://en.wikipedia.org/wiki/De_Bruijn_index
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Term {
+    /// The primitive at the given index of the library.
+    Primitive(usize),
+    /// The variable with the given de Bruijn index.
+    Variable(usize),
+    /// The application of a function to an argument.
+    Application(Box<Term>, Box<Term>),
+    /// A lambda abstraction.
+    Abstraction(Box<Term>),
+}
+impl Term {
+    /// The number of primitives and variables in the term.
+    pub fn size(&self) -> usize {
+        match *self {
+            Term::Primitive(_) | Term::Variable(_) => 1,
+            Term::Application(ref f, ref x) => f.size() + x.size(),
+            Term::Abstraction(ref body) => body.size(),
+        }
+    }
+    /// Apply `f` to each of `args` in turn.
+    pub(crate) fn applied(f: Term, args: Vec<Term>) -> Term {
+        args.into_iter()
+            .fold(f, |f, x| Term::Application(Box::new(f), Box::new(x)))
+    }
+    /// Show the term with each primitive by its name in `names`, which is
+    /// indexed like the library. Primitives without a name are shown by
+    /// index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::Term;
+    /// let t = Term::Abstraction(Box::new(Term::Application(
+    ///     Box::new(Term::Primitive(0)),
+    ///     Box::new(Term::Application(
+    ///         Box::new(Term::Primitive(1)),
+    ///         Box::new(Term::Variable(0)),
+    ///     )),
+    /// )));
+    /// assert_eq!(t.display_with_names(&["negate", "length"]), "(λ (negate (length $0)))");
+    /// assert_eq!(t.display_with_names(&["negate"]), "(λ (negate (#1 $0)))");
+    /// ```
+    pub fn display_with_names<S: AsRef<str>>(&self, names: &[S]) -> String {
+        self.show(false, &|p| match names.get(p) {
+            Some(name) => name.as_ref().to_string(),
+            None => format!("#{}", p),
+        })
+    }
+    fn show(&self, is_function: bool, primitive: &dyn Fn(usize) -> String) -> String {
+        match *self {
+            Term::Primitive(p) => primitive(p),
+            Term::Variable(v) => format!("${}", v),
+            Term::Application(ref f, ref x) if is_function => {
+                format!("{} {}", f.show(true, primitive), x.show(false, primitive))
+            }
+            Term::Application(ref f, ref x) => {
+                format!("({} {})", f.show(true, primitive), x.show(false, primitive))
+            }
+            Term::Abstraction(ref body) => format!("(λ {})", body.show(false, primitive)),
+        }
+    }
+}
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.show(false, &|p| format!("#{}", p)))
+    }
+}
@@ -0,0 +1,98 @@
+use crate::effect::{is_effects, unify_rows};
+use crate::nat::{is_nat, unify_nats};
+use crate::units::{is_dimension, unify_dimensions};
+#[cfg(feature = "persistent")]
+use crate::PersistentContext;
+use crate::{Context, Name, Type, UnificationError, Variable};
+
+/// Pairs of types to unify.
+pub(crate) type Pairs<N> = Vec<(Type<N>, Type<N>)>;
+
+/// The operations on a context needed to unify modulo the theories of effect
+/// rows, units of measure, and type-level naturals.
+pub(crate) trait Unifier<N: Name> {
+    /// If `t` is a variable with a substitution, the substituted type.
+    fn resolve(&self, t: Type<N>) -> Type<N>;
+    fn apply(&self, t: &Type<N>) -> Type<N>;
+    fn new_variable(&mut self) -> Type<N>;
+    fn extend(&mut self, v: Variable, t: Type<N>);
+}
+impl<N: Name> Unifier<N> for Context<N> {
+    fn resolve(&self, t: Type<N>) -> Type<N> {
+        Context::resolve(self, t)
+    }
+    fn apply(&self, t: &Type<N>) -> Type<N> {
+        t.apply(self)
+    }
+    fn new_variable(&mut self) -> Type<N> {
+        Context::new_variable(self)
+    }
+    fn extend(&mut self, v: Variable, t: Type<N>) {
+        Context::extend(self, v, t)
+    }
+}
+#[cfg(feature = "persistent")]
+impl<N: Name> Unifier<N> for PersistentContext<N> {
+    fn resolve(&self, t: Type<N>) -> Type<N> {
+        PersistentContext::resolve(self, t)
+    }
+    fn apply(&self, t: &Type<N>) -> Type<N> {
+        PersistentContext::apply(self, t)
+    }
+    fn new_variable(&mut self) -> Type<N> {
+        PersistentContext::new_variable(self)
+    }
+    fn extend(&mut self, v: Variable, t: Type<N>) {
+        PersistentContext::extend(self, v, t)
+    }
+}
+
+/// Push onto `pending` the pairs of types which must unify for `name(a1…)`
+/// and `name(a2…)` to unify, so that they are popped in order. Pure arrows
+/// are given empty effects to match effectful ones, effect rows are unified
+/// regardless of the order of their labels, and dimensions and naturals are
+/// solved directly, leaving no pairs.
+pub(crate) fn unify_args<N: Name, U: Unifier<N>>(
+    ctx: &mut U,
+    name: N,
+    mut a1: Vec<Type<N>>,
+    mut a2: Vec<Type<N>>,
+    pending: &mut Pairs<N>,
+) -> Result<(), UnificationError<N>> {
+    if is_effects(&name) {
+        let pairs = unify_rows(ctx, name, a1, a2)?;
+        pending.extend(pairs.into_iter().rev());
+        return Ok(());
+    }
+    if is_dimension(&name) {
+        return unify_dimensions(ctx, name, a1, a2);
+    }
+    if is_nat(&name) {
+        return unify_nats(ctx, name, a1, a2);
+    }
+    if name.is_arrow() && a1.len() != a2.len() {
+        if let Some(empty) = Type::try_row(vec![], None) {
+            for args in &mut [&mut a1, &mut a2] {
+                if args.len() == 2 {
+                    args.push(empty.clone())
+                }
+            }
+        }
+    }
+    pending.extend(a1.into_iter().zip(a2).rev());
+    Ok(())
+}
+
+/// The leaf naming the number `n`, for the constants, coefficients, and
+/// exponents of naturals and dimensions.
+pub(crate) fn number<N: Name>(n: usize) -> Option<Type<N>> {
+    Some(Type::Constructed(N::number(n)?, vec![]))
+}
+
+/// The number named by a leaf made with [`number`].
+pub(crate) fn as_number<N: Name>(t: &Type<N>) -> Option<usize> {
+    match *t {
+        Type::Constructed(ref n, ref args) if args.is_empty() => n.as_number(),
+        _ => None,
+    }
+}
@@ -1,8 +1,11 @@
 use itertools::Itertools;
+use std::borrow::Cow;
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
-use crate::{Context, Name};
+use crate::visit::{rebuild, Rebuilder};
+use crate::{Context, Name, PrettyConfig, TypeFolder, VariableNaming};
 
 /// Represents a [type variable][1] (an unknown type).
 ///
@@ -99,6 +102,55 @@ impl<N: Name> TypeSchema<N> {
             }
         }
     }
+    /// Show the [`TypeSchema`] with its variables named according to
+    /// `naming`. Bound variables are named first, followed by free variables
+    /// in order of appearance, so every variable keeps one name throughout the
+    /// schema. Unless `naming` is [`VariableNaming::Numbered`], consecutive
+    /// quantifiers are grouped under a single `∀`. [`TypeSchema::parse_named`]
+    /// reads back schemas without free variables: free variables are not
+    /// quantified, so their names read back as constructors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp, VariableNaming};
+    /// let t = ptp!(4, 2; @arrow[tp!(@arrow[tp!(2), tp!(4)]), tp!(list(tp!(2))), tp!(7)]);
+    /// assert_eq!(t.to_string(), "∀t4. ∀t2. (t2 → t4) → list(t2) → t7");
+    /// assert_eq!(
+    ///     t.show_named(VariableNaming::Latin),
+    ///     "∀a b. (b → a) → list(b) → c",
+    /// );
+    /// assert_eq!(
+    ///     t.show_named(VariableNaming::Greek),
+    ///     "∀α β. (β → α) → list(β) → γ",
+    /// );
+    /// ```
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`TypeSchema::parse_named`]: enum.TypeSchema.html#method.parse_named
+    /// [`VariableNaming::Numbered`]: enum.VariableNaming.html#variant.Numbered
+    pub fn show_named(&self, naming: VariableNaming) -> String {
+        self.display_with(&PrettyConfig {
+            naming,
+            ..PrettyConfig::default()
+        })
+    }
+    /// Rename variables, including bound ones, according to `renaming`.
+    #[cfg_attr(not(feature = "parser"), allow(dead_code))]
+    pub(crate) fn rename_vars(&mut self, renaming: &HashMap<Variable, Variable>) {
+        match *self {
+            TypeSchema::Monotype(ref mut t) => t.rename_vars(renaming),
+            TypeSchema::Polytype {
+                ref mut variable,
+                ref mut body,
+            } => {
+                if let Some(&v) = renaming.get(variable) {
+                    *variable = v;
+                }
+                body.rename_vars(renaming)
+            }
+        }
+    }
     /// Instantiate a [`TypeSchema`] in the context by removing quantifiers.
     ///
     /// All type variables will be replaced with fresh type variables.
@@ -157,6 +209,127 @@ impl<N: Name> TypeSchema<N> {
             }
         }
     }
+    /// Substitute for the free variables of the [`TypeSchema`]. Bound
+    /// variables shadow the substitution, and quantifiers which would capture
+    /// a free variable of a substituted type are renamed to unused variables.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use polytype::{ptp, tp};
+    /// let t = ptp!(0; @arrow[tp!(0), tp!(1)]); // ∀α. α → β
+    ///
+    /// let mut substitution = HashMap::new();
+    /// substitution.insert(0, tp!(int));
+    /// substitution.insert(1, tp!(list(tp!(0))));
+    ///
+    /// // the quantified t0 is renamed so the substituted t0 stays free
+    /// assert_eq!(t.substitute(&substitution).to_string(), "∀t2. t2 → list(t0)");
+    /// ```
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    pub fn substitute(&self, substitution: &HashMap<Variable, Type<N>>) -> TypeSchema<N> {
+        self.substitute_with(|v| substitution.get(&v))
+    }
+    pub(crate) fn substitute_with<'a, F>(&self, lookup: F) -> TypeSchema<N>
+    where
+        F: Fn(Variable) -> Option<&'a Type<N>>,
+        N: 'a,
+    {
+        let free = self.free_vars();
+        let mut avoid: Vec<Variable> = free
+            .iter()
+            .filter_map(|&v| lookup(v))
+            .flat_map(Type::vars)
+            .collect();
+        avoid.sort_unstable();
+        avoid.dedup();
+        let bound = self.bound_vars();
+        let next = avoid
+            .iter()
+            .chain(&bound)
+            .chain(&free)
+            .max()
+            .map_or(0, |&v| v + 1);
+        self.fold(&mut Substitute {
+            lookup,
+            avoid,
+            next,
+        })
+    }
+    /// Apply the [`Context`]'s substitutions to the free variables of the
+    /// [`TypeSchema`], renaming quantifiers as in [`substitute`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp, Context};
+    /// let mut ctx = Context::default();
+    /// ctx.extend(0, tp!(int));
+    /// ctx.extend(1, tp!(list(tp!(0))));
+    /// ctx.extend(2, tp!(list(tp!(3))));
+    ///
+    /// let t = ptp!(0, 3; @arrow[tp!(0), tp!(3), tp!(1), tp!(2)]);
+    /// assert_eq!(t.apply(&ctx).to_string(), "∀t0. ∀t4. t0 → t4 → list(int) → list(t3)");
+    /// ```
+    ///
+    /// [`Context`]: struct.Context.html
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`substitute`]: #method.substitute
+    pub fn apply(&self, ctx: &Context<N>) -> TypeSchema<N> {
+        let substitution: HashMap<_, _> = self
+            .free_vars()
+            .into_iter()
+            .filter(|v| ctx.substitution.contains_key(v))
+            .map(|v| (v, Type::Variable(v).apply(ctx)))
+            .collect();
+        self.substitute(&substitution)
+    }
+}
+/// Substitutes for free variables, renaming binders in `avoid`.
+struct Substitute<F> {
+    lookup: F,
+    avoid: Vec<Variable>,
+    next: Variable,
+}
+impl<'a, N: Name + 'a, F: Fn(Variable) -> Option<&'a Type<N>>> TypeFolder<N> for Substitute<F> {
+    fn fold_variable(&mut self, v: Variable) -> Type<N> {
+        match (self.lookup)(v) {
+            Some(t) => t.clone(),
+            None => Type::Variable(v),
+        }
+    }
+    fn fold_binder(&mut self, v: Variable) -> Variable {
+        if self.avoid.binary_search(&v).is_ok() {
+            self.next += 1;
+            self.next - 1
+        } else {
+            v
+        }
+    }
+}
+/// Substitutes variables by a context, compressing the paths it follows.
+struct Apply<'a, N: Name>(&'a Context<N>);
+impl<'a, N: Name> Rebuilder<'a, N, N> for Apply<'a, N> {
+    fn constructed(&mut self, name: &N, args: Vec<Type<N>>) -> Type<N> {
+        Type::Constructed(name.clone(), args)
+    }
+    fn variable(&mut self, v: Variable) -> Result<Type<N>, Cow<'a, Type<N>>> {
+        if let Some(tp) = self.0.path_compression_cache.borrow().get(&v) {
+            return Err(Cow::Owned(tp.clone()));
+        }
+        match self.0.substitution.get(&v) {
+            Some(tp) => Err(Cow::Borrowed(tp)),
+            None => Ok(Type::Variable(v)),
+        }
+    }
+    fn replaced(&mut self, v: Variable, tp: &Type<N>) {
+        let mut cache = self.0.path_compression_cache.borrow_mut();
+        if cache.get(&v) != Some(tp) {
+            cache.insert(v, tp.clone());
+        }
+    }
 }
 impl<N: Name> fmt::Display for TypeSchema<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
@@ -177,7 +350,6 @@ impl<N: Name> fmt::Display for TypeSchema<N> {
 /// [`TypeSchema::instantiate`]: enum.TypeSchema.html#method.instantiate
 /// [`Type::arrow`]: enum.TypeSchema.html#method.instantiate
 /// [1]: https://en.wikipedia.org/wiki/Hindley–Milner_type_system#Monotypes
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum Type<N: Name = &'static str> {
     /// Primitive or composite types (e.g. `int`, `List(α)`, `α → β`)
     ///
@@ -285,37 +457,105 @@ impl<N: Name> Type<N> {
         }
     }
     pub(crate) fn occurs(&self, v: Variable) -> bool {
-        match *self {
-            Type::Constructed(_, ref args) => args.iter().any(|t| t.occurs(v)),
-            Type::Variable(n) => n == v,
-        }
+        self.any(|t| matches!(*t, Type::Variable(n) if n == v))
     }
     /// Supplying `is_return` helps arrows look cleaner.
+    ///
+    /// Types still to show are kept on a stack rather than recursed into, so
+    /// that types of any depth can be shown.
     pub(crate) fn show(&self, is_return: bool) -> String {
+        let mut shown = String::new();
+        let mut stack = vec![Shown::Type(self, is_return)];
+        while let Some(part) = stack.pop() {
+            match part {
+                Shown::Text(text) => shown.push_str(&text),
+                Shown::Type(t, is_return) => {
+                    stack.extend(t.show_parts(is_return).into_iter().rev())
+                }
+            }
+        }
+        shown
+    }
+    /// The outermost constructor laid out as text, around the types within it.
+    fn show_parts(&self, is_return: bool) -> Vec<Shown<'_, N>> {
         match *self {
-            Type::Variable(v) => format!("t{}", v),
+            Type::Variable(v) => vec![Shown::Text(format!("t{}", v))],
             Type::Constructed(ref name, ref args) => {
-                if args.is_empty() {
-                    name.show()
+                if let Some((labels, tail)) = self.as_row() {
+                    Type::row_show(&labels, tail)
+                } else if let Some(factors) = self.as_dimension() {
+                    Type::dimension_show(&factors)
+                } else if let Some((terms, constant)) = self.as_nat() {
+                    Type::nat_show(&terms, constant)
+                } else if args.is_empty() {
+                    vec![Shown::Text(name.show())]
                 } else if name.is_arrow() {
                     Type::arrow_show(args, is_return)
+                } else if args.len() == 1 && N::list().as_ref() == Some(name) {
+                    Shown::enclosed("[", args.iter(), ",", "]")
+                } else if args.len() > 1 && N::tuple().as_ref() == Some(name) {
+                    Shown::enclosed("(", args.iter(), ",", ")")
                 } else {
-                    format!(
-                        "{}({})",
-                        name.show(),
-                        args.iter().map(|t| t.show(true)).join(",")
-                    )
+                    Shown::enclosed(format!("{}(", name.show()), args.iter(), ",", ")")
                 }
             }
         }
     }
     /// Show specifically for arrow types
-    fn arrow_show(args: &[Type<N>], is_return: bool) -> String {
-        if is_return {
-            format!("{} → {}", args[0].show(false), args[1].show(true))
-        } else {
-            format!("({} → {})", args[0].show(false), args[1].show(true))
+    fn arrow_show(args: &[Type<N>], is_return: bool) -> Vec<Shown<'_, N>> {
+        let effects = match args.get(2).and_then(Type::as_row) {
+            Some((ref labels, None)) if labels.is_empty() => vec![],
+            Some((labels, tail)) => Type::row_show(&labels, tail),
+            None => args.get(2).map_or_else(Vec::new, |t| {
+                Shown::enclosed("{", std::iter::once(t), "", "}")
+            }),
+        };
+        let mut parts = vec![];
+        if !is_return {
+            parts.push(Shown::Text("(".into()));
+        }
+        parts.push(Shown::Type(&args[0], false));
+        parts.push(Shown::Text(" →".into()));
+        parts.extend(effects);
+        parts.push(Shown::Text(" ".into()));
+        parts.push(Shown::Type(&args[1], true));
+        if !is_return {
+            parts.push(Shown::Text(")".into()));
+        }
+        parts
+    }
+    /// Show specifically for effect rows
+    fn row_show<'a>(labels: &[&'a Type<N>], tail: Option<&'a Type<N>>) -> Vec<Shown<'a, N>> {
+        let mut parts = Shown::separated(labels.iter().cloned(), ",");
+        match tail {
+            Some(tail) if labels.is_empty() => parts.push(Shown::Type(tail, true)),
+            Some(tail) => {
+                parts.push(Shown::Text(" | ".into()));
+                parts.push(Shown::Type(tail, true));
+            }
+            None => (),
         }
+        parts.insert(0, Shown::Text("{".into()));
+        parts.push(Shown::Text("}".into()));
+        parts
+    }
+    /// Show the type with its variables named according to `naming`, in order
+    /// of appearance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, VariableNaming};
+    /// let t = tp!(@arrow[tp!(@arrow[tp!(3), tp!(1)]), tp!(list(tp!(3))), tp!(list(tp!(1)))]);
+    /// assert_eq!(t.show_named(VariableNaming::Numbered), "(t3 → t1) → list(t3) → list(t1)");
+    /// assert_eq!(t.show_named(VariableNaming::Latin), "(a → b) → list(a) → list(b)");
+    /// assert_eq!(t.show_named(VariableNaming::Greek), "(α → β) → list(α) → list(β)");
+    /// ```
+    pub fn show_named(&self, naming: VariableNaming) -> String {
+        self.display_with(&PrettyConfig {
+            naming,
+            ..PrettyConfig::default()
+        })
     }
     /// If the type is an arrow, recursively get all curried function arguments.
     ///
@@ -352,23 +592,21 @@ impl<N: Name> Type<N> {
     }
     /// If the type is an arrow, recursively get all curried function arguments.
     pub fn args_destruct(self) -> Option<Vec<Type<N>>> {
-        match self {
-            Type::Constructed(n, mut args) if n.is_arrow() => {
-                let mut tps = Vec::with_capacity(1);
-                let mut tp = args.pop().unwrap();
-                tps.push(args.pop().unwrap());
-                loop {
-                    match tp {
-                        Type::Constructed(n, mut args) if n.is_arrow() => {
-                            tp = args.pop().unwrap();
-                            tps.push(args.pop().unwrap());
-                        }
-                        _ => break,
-                    }
-                }
-                Some(tps)
+        let mut tps = vec![];
+        let mut tp = self;
+        while let Type::Constructed(ref n, ref mut args) = tp {
+            if !n.is_arrow() {
+                break;
             }
-            _ => None,
+            args.truncate(2);
+            let beta = args.pop().unwrap();
+            tps.push(args.pop().unwrap());
+            tp = beta;
+        }
+        if tps.is_empty() {
+            None
+        } else {
+            Some(tps)
         }
     }
     /// If the type is an arrow, get its ultimate return type.
@@ -419,58 +657,38 @@ impl<N: Name> Type<N> {
     ///
     /// [`Context`]: struct.Context.html
     pub fn apply(&self, ctx: &Context<N>) -> Type<N> {
-        match *self {
-            Type::Constructed(ref name, ref args) => {
-                let args = args.iter().map(|t| t.apply(ctx)).collect();
-                Type::Constructed(name.clone(), args)
-            }
-            Type::Variable(v) => {
-                let maybe_tp = ctx
-                    .path_compression_cache
-                    .borrow()
-                    .get(&v)
-                    .or_else(|| ctx.substitution.get(&v))
-                    .cloned();
-                maybe_tp
-                    .map(|mut tp| {
-                        tp.apply_mut(ctx);
-                        if ctx.path_compression_cache.borrow().get(&v) != Some(&tp) {
-                            ctx.path_compression_cache
-                                .borrow_mut()
-                                .insert(v, tp.clone());
-                        }
-                        tp
-                    })
-                    .unwrap_or_else(|| self.clone())
-            }
-        }
+        rebuild(Cow::Borrowed(self), &mut Apply(ctx), RECURSION_LIMIT)
     }
     /// Like [`apply_compress`], but works in-place.
     ///
     /// [`apply_compress`]: #method.apply_compress
     pub fn apply_mut(&mut self, ctx: &Context<N>) {
+        self.apply_mut_within(ctx, RECURSION_LIMIT)
+    }
+    fn apply_mut_within(&mut self, ctx: &Context<N>, depth: usize) {
         match *self {
-            Type::Constructed(_, ref mut args) => {
+            Type::Constructed(_, ref mut args) if depth > 0 => {
                 for t in args {
-                    t.apply_mut(ctx)
+                    t.apply_mut_within(ctx, depth - 1)
                 }
+                return;
             }
-            Type::Variable(v) => {
-                let maybe_tp = ctx
-                    .path_compression_cache
-                    .borrow()
-                    .get(&v)
-                    .or_else(|| ctx.substitution.get(&v))
-                    .cloned();
-                *self = maybe_tp
-                    .map(|mut tp| {
-                        tp.apply_mut(ctx);
-                        ctx.path_compression_cache
-                            .borrow_mut()
-                            .insert(v, tp.clone());
-                        tp
-                    })
-                    .unwrap_or_else(|| self.clone());
+            Type::Variable(v) if ctx.substitution.contains_key(&v) => {
+                *self = rebuild(Cow::Borrowed(&*self), &mut Apply(ctx), depth);
+                return;
+            }
+            Type::Variable(_) => return,
+            Type::Constructed(..) => (),
+        }
+        let mut stack = vec![self];
+        while let Some(t) = stack.pop() {
+            match *t {
+                Type::Constructed(_, ref mut args) => stack.extend(args),
+                Type::Variable(v) => {
+                    if ctx.substitution.contains_key(&v) {
+                        *t = Type::Variable(v).apply(ctx)
+                    }
+                }
             }
         }
     }
@@ -531,14 +749,20 @@ impl<N: Name> Type<N> {
         vars.dedup();
         vars
     }
-    fn vars_internal(&self, vars: &mut Vec<Variable>) {
-        match *self {
-            Type::Constructed(_, ref args) => {
-                for arg in args {
-                    arg.vars_internal(vars);
-                }
+    /// The variables present in a type, in order of first appearance.
+    pub(crate) fn vars_in_order(&self) -> Vec<Variable> {
+        let mut vars = vec![];
+        self.vars_internal(&mut vars);
+        vars.into_iter().unique().collect()
+    }
+    /// Push every occurrence of a variable in the type, in order.
+    pub(crate) fn vars_internal(&self, vars: &mut Vec<Variable>) {
+        let mut stack = vec![self];
+        while let Some(t) = stack.pop() {
+            match *t {
+                Type::Constructed(_, ref args) => stack.extend(args.iter().rev()),
+                Type::Variable(v) => vars.push(v),
             }
-            Type::Variable(v) => vars.push(v),
         }
     }
     /// Perform a substitution. This is analogous to [`apply`].
@@ -561,52 +785,273 @@ impl<N: Name> Type<N> {
     ///
     /// [`apply`]: #method.apply
     pub fn substitute(&self, substitution: &HashMap<Variable, Type<N>>) -> Type<N> {
-        match *self {
-            Type::Constructed(ref name, ref args) => {
-                let args = args.iter().map(|t| t.substitute(substitution)).collect();
-                Type::Constructed(name.clone(), args)
-            }
-            Type::Variable(v) => substitution.get(&v).cloned().unwrap_or(Type::Variable(v)),
-        }
+        self.map_vars(|v| substitution.get(&v).cloned().unwrap_or(Type::Variable(v)))
     }
     /// Like [`substitute`], but works in-place.
     ///
     /// [`substitute`]: #method.substitute
     pub fn substitute_mut(&mut self, substitution: &HashMap<Variable, Type<N>>) {
+        let mut stack = vec![self];
+        while let Some(t) = stack.pop() {
+            match *t {
+                Type::Constructed(_, ref mut args) => stack.extend(args),
+                Type::Variable(v) => {
+                    if let Some(tp) = substitution.get(&v) {
+                        *t = tp.clone()
+                    }
+                }
+            }
+        }
+    }
+    /// Rename variables according to `renaming`.
+    #[cfg_attr(not(feature = "parser"), allow(dead_code))]
+    pub(crate) fn rename_vars(&mut self, renaming: &HashMap<Variable, Variable>) {
+        let mut stack = vec![self];
+        while let Some(t) = stack.pop() {
+            match *t {
+                Type::Constructed(_, ref mut args) => stack.extend(args),
+                Type::Variable(ref mut v) => {
+                    if let Some(&w) = renaming.get(v) {
+                        *v = w
+                    }
+                }
+            }
+        }
+    }
+}
+// Cloning, comparing, and hashing types recurse only so deep, and continue
+// without recursion below that, so that they work on types of any depth while
+// staying cheap on the small types which are most common.
+pub(crate) const RECURSION_LIMIT: usize = 64;
+impl<N: Name> Type<N> {
+    fn clone_within(&self, depth: usize) -> Self {
+        match *self {
+            Type::Variable(v) => Type::Variable(v),
+            Type::Constructed(ref name, ref args) if depth > 0 => Type::Constructed(
+                name.clone(),
+                args.iter().map(|t| t.clone_within(depth - 1)).collect(),
+            ),
+            Type::Constructed(..) => self.map_vars(Type::Variable),
+        }
+    }
+    fn eq_within(&self, other: &Self, depth: usize) -> bool {
+        match (self, other) {
+            (Type::Variable(v1), Type::Variable(v2)) => v1 == v2,
+            (Type::Constructed(n1, a1), Type::Constructed(n2, a2)) if depth > 0 => {
+                n1 == n2
+                    && a1.len() == a2.len()
+                    && a1
+                        .iter()
+                        .zip(a2)
+                        .all(|(t1, t2)| t1.eq_within(t2, depth - 1))
+            }
+            _ => {
+                let mut pairs = vec![(self, other)];
+                while let Some(pair) = pairs.pop() {
+                    match pair {
+                        (Type::Variable(v1), Type::Variable(v2)) if v1 == v2 => (),
+                        (Type::Constructed(n1, a1), Type::Constructed(n2, a2))
+                            if n1 == n2 && a1.len() == a2.len() =>
+                        {
+                            pairs.extend(a1.iter().zip(a2))
+                        }
+                        _ => return false,
+                    }
+                }
+                true
+            }
+        }
+    }
+    fn hash_within<H: Hasher>(&self, state: &mut H, depth: usize)
+    where
+        N: Hash,
+    {
         match *self {
-            Type::Constructed(_, ref mut args) => {
+            Type::Constructed(ref name, ref args) if depth > 0 => {
+                state.write_u8(0);
+                name.hash(state);
+                state.write_usize(args.len());
                 for t in args {
-                    t.substitute_mut(substitution)
+                    t.hash_within(state, depth - 1)
                 }
             }
             Type::Variable(v) => {
-                if let Some(t) = substitution.get(&v) {
-                    *self = t.clone()
+                state.write_u8(1);
+                v.hash(state);
+            }
+            Type::Constructed(..) => {
+                let mut stack = vec![self];
+                while let Some(t) = stack.pop() {
+                    match *t {
+                        Type::Constructed(ref name, ref args) => {
+                            state.write_u8(0);
+                            name.hash(state);
+                            state.write_usize(args.len());
+                            stack.extend(args.iter().rev());
+                        }
+                        Type::Variable(v) => {
+                            state.write_u8(1);
+                            v.hash(state);
+                        }
+                    }
                 }
             }
         }
     }
 }
+impl<N: Name> Clone for Type<N> {
+    fn clone(&self) -> Self {
+        self.clone_within(RECURSION_LIMIT)
+    }
+}
+impl<N: Name> PartialEq for Type<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.eq_within(other, RECURSION_LIMIT)
+    }
+}
+impl<N: Name> Eq for Type<N> {}
+impl<N: Name + Hash> Hash for Type<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.hash_within(state, RECURSION_LIMIT)
+    }
+}
+/// Part of a shown type: text, or a type to show in its place.
+pub(crate) enum Shown<'a, N: Name> {
+    Text(String),
+    /// A type, and whether it is the return of an arrow.
+    Type(&'a Type<N>, bool),
+}
+impl<'a, N: Name> Shown<'a, N> {
+    /// `types` with `sep` between them.
+    pub(crate) fn separated<I: IntoIterator<Item = &'a Type<N>>>(types: I, sep: &str) -> Vec<Self> {
+        let mut parts = vec![];
+        for t in types {
+            if !parts.is_empty() {
+                parts.push(Shown::Text(sep.to_string()));
+            }
+            parts.push(Shown::Type(t, true));
+        }
+        parts
+    }
+    /// `types` with `sep` between them, between `open` and `close`.
+    fn enclosed<S: Into<String>, I: IntoIterator<Item = &'a Type<N>>>(
+        open: S,
+        types: I,
+        sep: &str,
+        close: &str,
+    ) -> Vec<Self> {
+        let mut parts = vec![Shown::Text(open.into())];
+        parts.extend(Shown::separated(types, sep));
+        parts.push(Shown::Text(close.to_string()));
+        parts
+    }
+    /// Join `parts`, showing each type with `show`.
+    pub(crate) fn join<F: FnMut(&Type<N>) -> String>(parts: Vec<Self>, mut show: F) -> String {
+        parts
+            .into_iter()
+            .map(|part| match part {
+                Shown::Text(text) => text,
+                Shown::Type(t, _) => show(t),
+            })
+            .collect()
+    }
+}
+/// Drop `t`, moving arguments below `RECURSION_LIMIT` onto a stack of their
+/// own so that types of any depth can be dropped.
+pub(crate) fn dismantle<N: Name>(t: Type<N>) {
+    dismantle_within(t, RECURSION_LIMIT)
+}
+fn dismantle_within<N: Name>(t: Type<N>, depth: usize) {
+    match t {
+        Type::Constructed(_, args) if depth > 0 => {
+            for t in args {
+                dismantle_within(t, depth - 1)
+            }
+        }
+        Type::Constructed(_, args) => {
+            let mut stack = args;
+            while let Some(t) = stack.pop() {
+                if let Type::Constructed(_, args) = t {
+                    stack.extend(args)
+                }
+            }
+        }
+        Type::Variable(_) => (),
+    }
+}
+// Formatted as if derived, but without recursion.
+impl<N: Name + fmt::Debug> fmt::Debug for Type<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        enum Task<'a, N: Name> {
+            Visit(&'a Type<N>, usize),
+            Write(&'static str),
+            Indent(usize),
+        }
+        fn indent(f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+            (0..depth).try_for_each(|_| f.write_str("    "))
+        }
+        let pretty = f.alternate();
+        let mut tasks = vec![Task::Visit(self, 0)];
+        while let Some(task) = tasks.pop() {
+            // the rest of the current type, in order
+            let mut rest = vec![];
+            match task {
+                Task::Write(s) => f.write_str(s)?,
+                Task::Indent(depth) => indent(f, depth)?,
+                Task::Visit(Type::Variable(v), depth) if pretty => {
+                    f.write_str("Variable(\n")?;
+                    indent(f, depth + 1)?;
+                    writeln!(f, "{:?},", v)?;
+                    indent(f, depth)?;
+                    f.write_str(")")?;
+                }
+                Task::Visit(Type::Variable(v), _) => write!(f, "Variable({:?})", v)?,
+                Task::Visit(Type::Constructed(name, args), depth) if pretty => {
+                    f.write_str("Constructed(\n")?;
+                    indent(f, depth + 1)?;
+                    fmt::Debug::fmt(name, f)?;
+                    f.write_str(",\n")?;
+                    indent(f, depth + 1)?;
+                    f.write_str("[")?;
+                    if !args.is_empty() {
+                        rest.push(Task::Write("\n"));
+                        for arg in args {
+                            rest.push(Task::Indent(depth + 2));
+                            rest.push(Task::Visit(arg, depth + 2));
+                            rest.push(Task::Write(",\n"));
+                        }
+                        rest.push(Task::Indent(depth + 1));
+                    }
+                    rest.push(Task::Write("],\n"));
+                    rest.push(Task::Indent(depth));
+                    rest.push(Task::Write(")"));
+                }
+                Task::Visit(Type::Constructed(name, args), _) => {
+                    f.write_str("Constructed(")?;
+                    fmt::Debug::fmt(name, f)?;
+                    f.write_str(", [")?;
+                    for (i, arg) in args.iter().enumerate() {
+                        if i > 0 {
+                            rest.push(Task::Write(", "));
+                        }
+                        rest.push(Task::Visit(arg, 0));
+                    }
+                    rest.push(Task::Write("])"));
+                }
+            }
+            tasks.extend(rest.into_iter().rev());
+        }
+        Ok(())
+    }
+}
 impl<N: Name> fmt::Display for Type<N> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(f, "{}", self.show(true))
     }
 }
 impl<N: Name> From<VecDeque<Type<N>>> for Type<N> {
-    fn from(mut tps: VecDeque<Type<N>>) -> Type<N> {
-        match tps.len() {
-            0 => panic!("cannot create a type from nothing"),
-            1 => tps.pop_front().unwrap(),
-            2 => {
-                let alpha = tps.pop_front().unwrap();
-                let beta = tps.pop_front().unwrap();
-                Type::arrow(alpha, beta)
-            }
-            _ => {
-                let alpha = tps.pop_front().unwrap();
-                Type::arrow(alpha, tps.into())
-            }
-        }
+    fn from(tps: VecDeque<Type<N>>) -> Type<N> {
+        Vec::from(tps).into()
     }
 }
 impl<N: Name> From<Vec<Type<N>>> for Type<N> {
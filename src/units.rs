@@ -0,0 +1,295 @@
+use std::convert::TryFrom;
+
+use crate::theory::{as_number, number, Unifier};
+use crate::types::Shown;
+use crate::{Name, Type, UnificationError};
+
+impl<N: Name> Type<N> {
+    /// Construct a dimension, or unit of measure (e.g. `m·s^-1`), from base
+    /// units and dimension variables raised to integer exponents.
+    ///
+    /// Dimensions are unified modulo the theory of abelian groups, so
+    /// `m·s^-1` unifies with `s^-1·m`, and `t0^2` with `m^2·s^-2` by taking
+    /// `t0` to be `m·s^-1`. Dimension variables are ordinary
+    /// [`Type::Variable`]s, so a [`TypeSchema`] may quantify over them.
+    /// Dimensions are displayed normalized, with positive exponents first and
+    /// factors otherwise in order of appearance, but are only equal as types
+    /// if built alike.
+    ///
+    /// # Panics
+    ///
+    /// If the [`Name`] has no [`dimension`] or [`number`] constructor, or if
+    /// an exponent overflows an `i32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp, Context, Type};
+    /// let speed = Type::dimension(vec![(tp!(m), 1), (tp!(s), -1)]);
+    /// assert_eq!(tp!(float(speed.clone())).to_string(), "float(m·s^-1)");
+    ///
+    /// let mut ctx = Context::default();
+    /// let per_second = Type::dimension(vec![(tp!(s), -1), (tp!(m), 1), (tp!(s), 0)]);
+    /// ctx.unify(&speed, &per_second).expect("unifies");
+    ///
+    /// // sqr: ∀u. float(u) → float(u^2)
+    /// let sqr = ptp!(0; @arrow[
+    ///     tp!(float(tp!(0))),
+    ///     tp!(float(Type::dimension(vec![(tp!(0), 2)]))),
+    /// ]);
+    /// let sqr = sqr.instantiate(&mut ctx);
+    /// let ret = ctx.new_variable();
+    /// ctx.unify(&sqr, &tp!(@arrow[tp!(float(speed)), ret.clone()])).expect("unifies");
+    /// assert_eq!(ret.apply(&ctx).to_string(), "float(m^2·s^-2)");
+    ///
+    /// // the square root of an area, in an unknown dimension u
+    /// let area = Type::dimension(vec![(tp!(m), 2)]);
+    /// let u = ctx.new_variable();
+    /// let u2 = Type::dimension(vec![(u.clone(), 2)]);
+    /// ctx.unify(&u2, &area).expect("unifies");
+    /// assert_eq!(u.apply(&ctx).to_string(), "m");
+    /// let volume = Type::dimension(vec![(tp!(m), 3)]);
+    /// assert!(ctx.unify(&u2, &volume).is_err());
+    /// ```
+    ///
+    /// [`Type::Variable`]: #variant.Variable
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`Name`]: trait.Name.html
+    /// [`dimension`]: trait.Name.html#method.dimension
+    /// [`number`]: trait.Name.html#method.number
+    pub fn dimension(factors: Vec<(Type<N>, i32)>) -> Type<N> {
+        let factors = factors.into_iter().map(|(t, e)| (t, i64::from(e)));
+        Type::try_dimension(factors.collect())
+            .expect("the name has no constructor for dimensions, or an exponent overflows")
+    }
+    /// The dimension `Π t^e`, combining equal factors, if each exponent fits
+    /// an `i32`. Factors are kept on the side of their exponent's sign, under
+    /// its magnitude as a [`Name::number`]: `·(·(·(k1, t1), …), ·(·(j1, u1), …))`
+    /// for `t1^k1 · … · u1^-j1 · …`.
+    ///
+    /// [`Name::number`]: trait.Name.html#method.number
+    fn try_dimension(factors: Factors<N>) -> Option<Type<N>> {
+        let name = N::dimension()?;
+        let mut combined = vec![];
+        for (t, e) in factors {
+            add_factor(&mut combined, t, e)?;
+        }
+        let (mut numerator, mut denominator) = (vec![], vec![]);
+        for (t, e) in combined {
+            if i32::try_from(e).is_err() {
+                return None;
+            }
+            let side = if e > 0 {
+                &mut numerator
+            } else {
+                &mut denominator
+            };
+            let k = usize::try_from(e.unsigned_abs()).ok()?;
+            side.push(Type::Constructed(name.clone(), vec![number(k)?, t]));
+        }
+        Some(Type::Constructed(
+            name.clone(),
+            vec![
+                Type::Constructed(name.clone(), numerator),
+                Type::Constructed(name, denominator),
+            ],
+        ))
+    }
+    /// If the type is a dimension, its base units and dimension variables
+    /// with their exponents, normalized: each appears once, in order of
+    /// appearance, with a nonzero exponent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Type};
+    /// let t = Type::dimension(vec![(tp!(m), 1), (tp!(s), -1), (tp!(m), 1)]);
+    /// assert_eq!(t.as_dimension(), Some(vec![(&tp!(m), 2), (&tp!(s), -1)]));
+    /// assert_eq!(Type::<&str>::dimension(vec![]).to_string(), "1");
+    /// ```
+    pub fn as_dimension(&self) -> Option<Vec<(&Type<N>, i32)>> {
+        let mut factors: Vec<(&Type<N>, i64)> = vec![];
+        let mut stack = vec![(self.dimension_parts()?, 1)];
+        while let Some(((numerator, denominator), scale)) = stack.pop() {
+            let parts = numerator.into_iter().map(|(k, t)| (k, t, false));
+            let parts = parts.chain(denominator.into_iter().map(|(k, t)| (k, t, true)));
+            for (k, t, inverse) in parts {
+                let e = i64::try_from(k).ok()?.checked_mul(scale)?;
+                let e = if inverse { e.checked_neg()? } else { e };
+                match t.dimension_parts() {
+                    Some(nested) => stack.push((nested, e)),
+                    None => match factors.iter_mut().find(|(f, _)| *f == t) {
+                        Some((_, exponent)) => *exponent = exponent.checked_add(e)?,
+                        None => factors.push((t, e)),
+                    },
+                }
+            }
+        }
+        factors
+            .into_iter()
+            .filter(|&(_, e)| e != 0)
+            .map(|(t, e)| Some((t, i32::try_from(e).ok()?)))
+            .collect()
+    }
+    /// The numerator and denominator of a dimension, each factor under the
+    /// magnitude of its exponent.
+    #[allow(clippy::type_complexity)]
+    fn dimension_parts(&self) -> Option<(Vec<(usize, &Type<N>)>, Vec<(usize, &Type<N>)>)> {
+        match *self {
+            Type::Constructed(ref n, ref args) if is_dimension(n) && args.len() == 2 => {
+                match (&args[0], &args[1]) {
+                    (Type::Constructed(n1, num), Type::Constructed(n2, den))
+                        if is_dimension(n1) && is_dimension(n2) =>
+                    {
+                        Some((dimension_side(num)?, dimension_side(den)?))
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+    /// Show specifically for dimensions, with positive exponents first.
+    pub(crate) fn dimension_show<'a>(factors: &[(&'a Type<N>, i32)]) -> Vec<Shown<'a, N>> {
+        if factors.is_empty() {
+            return vec![Shown::Text(String::from("1"))];
+        }
+        let mut factors = factors.to_vec();
+        factors.sort_by_key(|&(_, e)| e < 0);
+        let mut parts = vec![];
+        for (t, e) in factors {
+            if !parts.is_empty() {
+                parts.push(Shown::Text(String::from("·")));
+            }
+            parts.push(Shown::Type(t, true));
+            if e != 1 {
+                parts.push(Shown::Text(format!("^{}", e)));
+            }
+        }
+        parts
+    }
+}
+
+pub(crate) fn is_dimension<N: Name>(name: &N) -> bool {
+    N::dimension().as_ref() == Some(name)
+}
+
+/// Factors with their exponents, widened so that negating and combining
+/// them is checked rather than wrapping at the bounds of an `i32`.
+type Factors<N> = Vec<(Type<N>, i64)>;
+
+/// The factors on one side of a dimension, with the magnitudes of their
+/// exponents.
+fn dimension_side<N: Name>(side: &[Type<N>]) -> Option<Vec<(usize, &Type<N>)>> {
+    side.iter()
+        .map(|factor| match *factor {
+            Type::Constructed(ref n, ref factor) if is_dimension(n) && factor.len() == 2 => {
+                Some((as_number(&factor[0])?, &factor[1]))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Multiply `factors` by `t^e`, unless an exponent overflows.
+fn add_factor<N: Name>(factors: &mut Factors<N>, t: Type<N>, e: i64) -> Option<()> {
+    match factors.iter().position(|(f, _)| *f == t) {
+        Some(i) => factors[i].1 = factors[i].1.checked_add(e)?,
+        None => factors.push((t, e)),
+    }
+    factors.retain(|&(_, e)| e != 0);
+    Some(())
+}
+
+/// The factors of `t`, which is a dimension or a dimension variable.
+fn factors<N: Name>(t: &Type<N>) -> Factors<N> {
+    match t.as_dimension() {
+        Some(factors) => factors
+            .into_iter()
+            .map(|(t, e)| (t.clone(), i64::from(e)))
+            .collect(),
+        None => vec![(t.clone(), 1)],
+    }
+}
+
+/// Unify the dimensions `name(a1…)` and `name(a2…)` by solving
+/// `name(a1…) · name(a2…)^-1 = 1` for their variables, as described by
+/// Kennedy in [Types for Units-of-Measure: Theory and Practice].
+///
+/// [Types for Units-of-Measure: Theory and Practice]: https://www.microsoft.com/en-us/research/publication/types-for-units-of-measure-theory-and-practice/
+pub(crate) fn unify_dimensions<N: Name, U: Unifier<N>>(
+    ctx: &mut U,
+    name: N,
+    a1: Vec<Type<N>>,
+    a2: Vec<Type<N>>,
+) -> Result<(), UnificationError<N>> {
+    let d1 = ctx.apply(&Type::Constructed(name.clone(), a1));
+    let d2 = ctx.apply(&Type::Constructed(name, a2));
+    if d1.as_dimension().is_none() || d2.as_dimension().is_none() {
+        return Err(UnificationError::Failure(d1, d2));
+    }
+    let mut u = factors(&d1);
+    for (t, e) in factors(&d2) {
+        if add_factor(&mut u, t, -e).is_none() {
+            return Err(UnificationError::Failure(d1, d2));
+        }
+    }
+    match solve(ctx, u) {
+        Some(()) => Ok(()),
+        None => Err(UnificationError::Failure(d1, d2)),
+    }
+}
+
+/// Solve `u = 1` for the variables of `u`, unless it has no solution or an
+/// exponent overflows.
+fn solve<N: Name, U: Unifier<N>>(ctx: &mut U, mut u: Factors<N>) -> Option<()> {
+    loop {
+        let (v, x) = match u
+            .iter()
+            .filter_map(|(t, e)| match *t {
+                Type::Variable(v) => Some((v, *e)),
+                _ => None,
+            })
+            .min_by_key(|&(_, e)| e.unsigned_abs())
+        {
+            Some(var) => var,
+            None if u.is_empty() => return Some(()),
+            None => return None,
+        };
+        // u = 1 exactly when u^-1 = 1, so make v's exponent positive.
+        if x < 0 {
+            for (_, e) in &mut u {
+                *e = e.checked_neg()?;
+            }
+        }
+        let x = x.checked_abs()?;
+        let others: Vec<_> = u
+            .iter()
+            .filter(|(t, _)| *t != Type::Variable(v))
+            .cloned()
+            .collect();
+        if others.iter().all(|&(_, e)| e % x == 0) {
+            let solution = others
+                .into_iter()
+                .map(|(t, e)| Some((t, (e / x).checked_neg()?)))
+                .collect::<Option<_>>()?;
+            ctx.extend(v, Type::try_dimension(solution)?);
+            return Some(());
+        }
+        if others.iter().all(|(t, _)| !matches!(*t, Type::Variable(_))) {
+            return None;
+        }
+        // v = β · Π w^-⌊e/x⌋ leaves each w with exponent e mod x in u.
+        let beta = ctx.new_variable();
+        let mut solution = vec![(beta, 1)];
+        for (t, e) in others {
+            solution.push((t, e.div_euclid(x).checked_neg()?));
+        }
+        let solution = Type::try_dimension(solution)?;
+        u.retain(|(t, _)| *t != Type::Variable(v));
+        for (t, e) in factors(&solution) {
+            add_factor(&mut u, t, x.checked_mul(e)?)?;
+        }
+        ctx.extend(v, solution);
+    }
+}
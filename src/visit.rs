@@ -0,0 +1,506 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::types::RECURSION_LIMIT;
+use crate::{Name, Type, TypeSchema, Variable};
+
+/// Inspects each part of a [`Type`] or [`TypeSchema`] in turn, as driven by
+/// [`Type::walk`] and [`TypeSchema::walk`].
+///
+/// Every method does nothing by default, so implement only those you need.
+/// Parts are visited in order of appearance, each constructed type before
+/// its arguments.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, TypeVisitor, Variable};
+/// #[derive(Default)]
+/// struct Counter {
+///     arrows: usize,
+///     free: Vec<Variable>,
+/// }
+/// impl TypeVisitor for Counter {
+///     fn visit_constructed(&mut self, name: &&'static str, _args: &[polytype::Type]) {
+///         if *name == "→" {
+///             self.arrows += 1
+///         }
+///     }
+///     fn visit_variable(&mut self, v: Variable) {
+///         self.free.push(v)
+///     }
+/// }
+///
+/// let mut counter = Counter::default();
+/// ptp!(0; @arrow[tp!(0), tp!(1), tp!(list(tp!(0)))]).walk(&mut counter);
+/// assert_eq!(counter.arrows, 2);
+/// assert_eq!(counter.free, vec![1]);
+/// ```
+///
+/// [`Type`]: enum.Type.html
+/// [`TypeSchema`]: enum.TypeSchema.html
+/// [`Type::walk`]: enum.Type.html#method.walk
+/// [`TypeSchema::walk`]: enum.TypeSchema.html#method.walk
+pub trait TypeVisitor<N: Name = &'static str> {
+    /// Visit a constructed type, before its arguments are visited.
+    fn visit_constructed(&mut self, _name: &N, _args: &[Type<N>]) {}
+    /// Visit an occurrence of a free variable.
+    fn visit_variable(&mut self, _v: Variable) {}
+    /// Visit a quantifier of a [`TypeSchema`], before its body is visited.
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    fn visit_binder(&mut self, _v: Variable) {}
+    /// Visit an occurrence of a variable bound by a quantifier.
+    fn visit_bound_variable(&mut self, _v: Variable) {}
+}
+
+/// Rebuilds a [`Type`] or [`TypeSchema`] from the bottom up, as driven by
+/// [`Type::fold`] and [`TypeSchema::fold`].
+///
+/// By default, every method rebuilds what it is given unchanged.
+///
+/// # Examples
+///
+/// ```
+/// # use polytype::{ptp, tp, Type, TypeFolder};
+/// // replace list(α) with α → bool
+/// struct Predicates;
+/// impl TypeFolder for Predicates {
+///     fn fold_constructed(&mut self, name: &&'static str, mut args: Vec<Type>) -> Type {
+///         if *name == "list" {
+///             Type::arrow(args.remove(0), tp!(bool))
+///         } else {
+///             Type::Constructed(name, args)
+///         }
+///     }
+/// }
+///
+/// let t = ptp!(0; @arrow[tp!(list(tp!(0))), tp!(list(tp!(list(tp!(int)))))]);
+/// assert_eq!(t.fold(&mut Predicates).to_string(), "∀t0. (t0 → bool) → (int → bool) → bool");
+/// ```
+///
+/// [`Type`]: enum.Type.html
+/// [`TypeSchema`]: enum.TypeSchema.html
+/// [`Type::fold`]: enum.Type.html#method.fold
+/// [`TypeSchema::fold`]: enum.TypeSchema.html#method.fold
+pub trait TypeFolder<N: Name = &'static str> {
+    /// Rebuild a constructed type from its already rebuilt arguments.
+    fn fold_constructed(&mut self, name: &N, args: Vec<Type<N>>) -> Type<N> {
+        Type::Constructed(name.clone(), args)
+    }
+    /// Rebuild an occurrence of a free variable.
+    fn fold_variable(&mut self, v: Variable) -> Type<N> {
+        Type::Variable(v)
+    }
+    /// Rename a quantifier of a [`TypeSchema`]. Occurrences of the variable it
+    /// binds are renamed with it, and are not passed to [`fold_variable`].
+    ///
+    /// [`TypeSchema`]: enum.TypeSchema.html
+    /// [`fold_variable`]: #method.fold_variable
+    fn fold_binder(&mut self, v: Variable) -> Variable {
+        v
+    }
+}
+
+/// Passes free variables to `folder` and renames bound ones.
+struct Binders<'a, F> {
+    folder: &'a mut F,
+    bound: HashMap<Variable, Variable>,
+}
+impl<'a, N: Name, F: TypeFolder<N>> TypeFolder<N> for Binders<'a, F> {
+    fn fold_constructed(&mut self, name: &N, args: Vec<Type<N>>) -> Type<N> {
+        self.folder.fold_constructed(name, args)
+    }
+    fn fold_variable(&mut self, v: Variable) -> Type<N> {
+        match self.bound.get(&v) {
+            Some(&w) => Type::Variable(w),
+            None => self.folder.fold_variable(v),
+        }
+    }
+}
+
+/// Replaces free variables.
+struct MapVars<F>(F);
+impl<N: Name, F: FnMut(Variable) -> Type<N>> TypeFolder<N> for MapVars<F> {
+    fn fold_variable(&mut self, v: Variable) -> Type<N> {
+        (self.0)(v)
+    }
+}
+
+/// Rebuilds a `Type<N>` as a `Type<M>` from the bottom up. This is the one
+/// traversal behind [`Type::fold`], [`Type::map_name`], [`Type::apply`], and
+/// [`PersistentContext::apply`].
+pub(crate) trait Rebuilder<'a, N: Name, M: Name> {
+    /// Rebuild a constructed type from its already rebuilt arguments.
+    fn constructed(&mut self, name: &N, args: Vec<Type<M>>) -> Type<M>;
+    /// Rebuild a variable, or give the type to rebuild in its place.
+    fn variable(&mut self, v: Variable) -> Result<Type<M>, Cow<'a, Type<N>>>;
+    /// Take note that `t` was rebuilt in place of `v`.
+    fn replaced(&mut self, _v: Variable, _t: &Type<M>) {}
+}
+
+/// Drives a [`TypeFolder`].
+struct Folding<'f, F>(&'f mut F);
+impl<'a, 'f, N: Name, F: TypeFolder<N>> Rebuilder<'a, N, N> for Folding<'f, F> {
+    fn constructed(&mut self, name: &N, args: Vec<Type<N>>) -> Type<N> {
+        self.0.fold_constructed(name, args)
+    }
+    fn variable(&mut self, v: Variable) -> Result<Type<N>, Cow<'a, Type<N>>> {
+        Ok(self.0.fold_variable(v))
+    }
+}
+
+/// Renames constructors.
+struct MapName<F>(F);
+impl<'a, N: Name, M: Name, F: FnMut(&N) -> M> Rebuilder<'a, N, M> for MapName<F> {
+    fn constructed(&mut self, name: &N, args: Vec<Type<M>>) -> Type<M> {
+        Type::Constructed((self.0)(name), args)
+    }
+    fn variable(&mut self, v: Variable) -> Result<Type<M>, Cow<'a, Type<N>>> {
+        Ok(Type::Variable(v))
+    }
+}
+
+/// Rebuild `t` with `rebuilder`, by recursion for the first `depth` levels,
+/// and without it below.
+pub(crate) fn rebuild<'a, N: Name, M: Name, R: Rebuilder<'a, N, M>>(
+    t: Cow<'a, Type<N>>,
+    rebuilder: &mut R,
+    depth: usize,
+) -> Type<M> {
+    if depth == 0 {
+        return rebuild_iteratively(t, rebuilder);
+    }
+    let v = match t {
+        Cow::Borrowed(t) => match *t {
+            Type::Constructed(ref name, ref args) => {
+                let args = args
+                    .iter()
+                    .map(|t| rebuild(Cow::Borrowed(t), rebuilder, depth - 1))
+                    .collect();
+                return rebuilder.constructed(name, args);
+            }
+            Type::Variable(v) => v,
+        },
+        Cow::Owned(Type::Constructed(name, args)) => {
+            let args = args
+                .into_iter()
+                .map(|t| rebuild(Cow::Owned(t), rebuilder, depth - 1))
+                .collect();
+            return rebuilder.constructed(&name, args);
+        }
+        Cow::Owned(Type::Variable(v)) => v,
+    };
+    match rebuilder.variable(v) {
+        Ok(t) => t,
+        Err(t) => {
+            let t = rebuild(t, rebuilder, depth - 1);
+            rebuilder.replaced(v, &t);
+            t
+        }
+    }
+}
+
+fn rebuild_iteratively<'a, N: Name, M: Name, R: Rebuilder<'a, N, M>>(
+    t: Cow<'a, Type<N>>,
+    rebuilder: &mut R,
+) -> Type<M> {
+    enum Task<'a, N: Name> {
+        Visit(Cow<'a, Type<N>>),
+        Build(Cow<'a, N>, usize),
+        Replaced(Variable),
+    }
+    let mut tasks = vec![Task::Visit(t)];
+    let mut built = vec![];
+    while let Some(task) = tasks.pop() {
+        let v = match task {
+            Task::Visit(Cow::Borrowed(t)) => match *t {
+                Type::Constructed(ref name, ref args) => {
+                    tasks.push(Task::Build(Cow::Borrowed(name), args.len()));
+                    tasks.extend(args.iter().rev().map(|t| Task::Visit(Cow::Borrowed(t))));
+                    continue;
+                }
+                Type::Variable(v) => v,
+            },
+            Task::Visit(Cow::Owned(Type::Constructed(name, args))) => {
+                tasks.push(Task::Build(Cow::Owned(name), args.len()));
+                tasks.extend(args.into_iter().rev().map(|t| Task::Visit(Cow::Owned(t))));
+                continue;
+            }
+            Task::Visit(Cow::Owned(Type::Variable(v))) => v,
+            Task::Build(name, n) => {
+                let args = built.split_off(built.len() - n);
+                built.push(rebuilder.constructed(&name, args));
+                continue;
+            }
+            Task::Replaced(v) => {
+                rebuilder.replaced(v, built.last().unwrap());
+                continue;
+            }
+        };
+        match rebuilder.variable(v) {
+            Ok(t) => built.push(t),
+            Err(t) => {
+                tasks.push(Task::Replaced(v));
+                tasks.push(Task::Visit(t));
+            }
+        }
+    }
+    built.pop().unwrap()
+}
+
+impl<N: Name> Type<N> {
+    /// Visit every part of the type with `visitor`.
+    ///
+    /// See [`TypeVisitor`] for an example.
+    ///
+    /// [`TypeVisitor`]: trait.TypeVisitor.html
+    pub fn walk<V: TypeVisitor<N>>(&self, visitor: &mut V) {
+        self.walk_bound(visitor, &[])
+    }
+    fn walk_bound<V: TypeVisitor<N>>(&self, visitor: &mut V, bound: &[Variable]) {
+        let mut stack = vec![self];
+        while let Some(t) = stack.pop() {
+            match *t {
+                Type::Constructed(ref name, ref args) => {
+                    visitor.visit_constructed(name, args);
+                    stack.extend(args.iter().rev());
+                }
+                Type::Variable(v) if bound.contains(&v) => visitor.visit_bound_variable(v),
+                Type::Variable(v) => visitor.visit_variable(v),
+            }
+        }
+    }
+    /// Rebuild the type with `folder`.
+    ///
+    /// See [`TypeFolder`] for an example.
+    ///
+    /// [`TypeFolder`]: trait.TypeFolder.html
+    pub fn fold<F: TypeFolder<N>>(&self, folder: &mut F) -> Type<N> {
+        rebuild(Cow::Borrowed(self), &mut Folding(folder), RECURSION_LIMIT)
+    }
+    /// Rename every constructor with `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::tp;
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// let t = t.map_names(|&name| if name == "list" { "vec" } else { name });
+    /// assert_eq!(t.to_string(), "int → vec(t0)");
+    /// ```
+    pub fn map_names<F: FnMut(&N) -> N>(&self, f: F) -> Type<N> {
+        self.map_name(f)
+    }
+    /// Convert the type to use another kind of [`Name`], renaming every
+    /// constructor with `f`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Name, Type};
+    /// #[derive(Clone, Debug, PartialEq, Eq)]
+    /// enum Builtin {
+    ///     Arrow,
+    ///     Int,
+    ///     Other(String),
+    /// }
+    /// impl Name for Builtin {
+    ///     fn arrow() -> Self {
+    ///         Builtin::Arrow
+    ///     }
+    /// }
+    ///
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// let t: Type<Builtin> = t.map_name(|&name| match name {
+    ///     "→" => Builtin::Arrow,
+    ///     "int" => Builtin::Int,
+    ///     other => Builtin::Other(other.to_string()),
+    /// });
+    /// assert_eq!(
+    ///     t,
+    ///     Type::arrow(
+    ///         Type::Constructed(Builtin::Int, vec![]),
+    ///         Type::Constructed(Builtin::Other("list".to_string()), vec![Type::Variable(0)]),
+    ///     ),
+    /// );
+    /// ```
+    ///
+    /// [`Name`]: trait.Name.html
+    pub fn map_name<M: Name, F: FnMut(&N) -> M>(&self, f: F) -> Type<M> {
+        rebuild(Cow::Borrowed(self), &mut MapName(f), RECURSION_LIMIT)
+    }
+    /// Replace every variable `v` with `f(v)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Type};
+    /// let t = tp!(@arrow[tp!(0), tp!(list(tp!(1)))]);
+    /// let t = t.map_vars(|v| if v == 0 { tp!(int) } else { Type::Variable(v + 1) });
+    /// assert_eq!(t.to_string(), "int → list(t2)");
+    /// ```
+    pub fn map_vars<F: FnMut(Variable) -> Type<N>>(&self, f: F) -> Type<N> {
+        self.fold(&mut MapVars(f))
+    }
+    /// Whether `p` holds for the type or any type within it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{tp, Type};
+    /// let t = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    /// assert!(t.any(|t| t.as_arrow().is_some()));
+    /// assert!(!t.any(|t| *t == tp!(bool)));
+    /// ```
+    pub fn any<P: FnMut(&Type<N>) -> bool>(&self, mut p: P) -> bool {
+        let mut stack = vec![self];
+        while let Some(t) = stack.pop() {
+            if p(t) {
+                return true;
+            }
+            if let Type::Constructed(_, ref args) = *t {
+                stack.extend(args.iter().rev())
+            }
+        }
+        false
+    }
+    /// The number of constructors and variables in the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::tp;
+    /// assert_eq!(tp!(@arrow[tp!(int), tp!(list(tp!(0)))]).size(), 4);
+    /// ```
+    pub fn size(&self) -> usize {
+        let mut size = 0;
+        self.any(|_| {
+            size += 1;
+            false
+        });
+        size
+    }
+    /// The greatest number of nested constructors and variables in the type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::tp;
+    /// assert_eq!(tp!(int).depth(), 1);
+    /// assert_eq!(tp!(@arrow[tp!(int), tp!(list(tp!(0)))]).depth(), 3);
+    /// ```
+    pub fn depth(&self) -> usize {
+        let mut deepest = 0;
+        let mut stack = vec![(self, 1)];
+        while let Some((t, depth)) = stack.pop() {
+            deepest = deepest.max(depth);
+            if let Type::Constructed(_, ref args) = *t {
+                stack.extend(args.iter().map(|t| (t, depth + 1)))
+            }
+        }
+        deepest
+    }
+}
+
+impl<N: Name> TypeSchema<N> {
+    /// Visit every part of the schema with `visitor`: each quantifier, then
+    /// the body, telling apart bound and free variables.
+    ///
+    /// See [`TypeVisitor`] for an example.
+    ///
+    /// [`TypeVisitor`]: trait.TypeVisitor.html
+    pub fn walk<V: TypeVisitor<N>>(&self, visitor: &mut V) {
+        let mut bound = vec![];
+        let mut schema = self;
+        loop {
+            match *schema {
+                TypeSchema::Monotype(ref t) => return t.walk_bound(visitor, &bound),
+                TypeSchema::Polytype { variable, ref body } => {
+                    visitor.visit_binder(variable);
+                    bound.push(variable);
+                    schema = body;
+                }
+            }
+        }
+    }
+    /// Rebuild the schema with `folder`. Quantifiers are renamed by
+    /// [`TypeFolder::fold_binder`], and only free variables are passed to
+    /// [`TypeFolder::fold_variable`].
+    ///
+    /// See [`TypeFolder`] for an example.
+    ///
+    /// [`TypeFolder`]: trait.TypeFolder.html
+    /// [`TypeFolder::fold_binder`]: trait.TypeFolder.html#method.fold_binder
+    /// [`TypeFolder::fold_variable`]: trait.TypeFolder.html#method.fold_variable
+    pub fn fold<F: TypeFolder<N>>(&self, folder: &mut F) -> TypeSchema<N> {
+        let mut binders = vec![];
+        let mut bound = HashMap::new();
+        let mut schema = self;
+        let body = loop {
+            match *schema {
+                TypeSchema::Monotype(ref t) => break t,
+                TypeSchema::Polytype { variable, ref body } => {
+                    let renamed = folder.fold_binder(variable);
+                    binders.push(renamed);
+                    bound.insert(variable, renamed);
+                    schema = body;
+                }
+            }
+        };
+        let body = body.fold(&mut Binders { folder, bound });
+        binders
+            .into_iter()
+            .rev()
+            .fold(TypeSchema::Monotype(body), |body, variable| {
+                TypeSchema::Polytype {
+                    variable,
+                    body: Box::new(body),
+                }
+            })
+    }
+    /// Rename every constructor with `f`.
+    pub fn map_names<F: FnMut(&N) -> N>(&self, f: F) -> TypeSchema<N> {
+        self.map_name(f)
+    }
+    /// Convert the schema to use another kind of [`Name`], renaming every
+    /// constructor with `f`. See [`Type::map_name`].
+    ///
+    /// [`Name`]: trait.Name.html
+    /// [`Type::map_name`]: enum.Type.html#method.map_name
+    pub fn map_name<M: Name, F: FnMut(&N) -> M>(&self, f: F) -> TypeSchema<M> {
+        let mut binders = vec![];
+        let mut schema = self;
+        let body = loop {
+            match *schema {
+                TypeSchema::Monotype(ref t) => break t.map_name(f),
+                TypeSchema::Polytype { variable, ref body } => {
+                    binders.push(variable);
+                    schema = body;
+                }
+            }
+        };
+        binders
+            .into_iter()
+            .rev()
+            .fold(TypeSchema::Monotype(body), |body, variable| {
+                TypeSchema::Polytype {
+                    variable,
+                    body: Box::new(body),
+                }
+            })
+    }
+    /// Replace every free variable `v` with `f(v)`, leaving bound variables
+    /// alone.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use polytype::{ptp, tp};
+    /// let t = ptp!(0; @arrow[tp!(0), tp!(1)]);
+    /// assert_eq!(t.map_vars(|_| tp!(int)).to_string(), "∀t0. t0 → int");
+    /// ```
+    pub fn map_vars<F: FnMut(Variable) -> Type<N>>(&self, f: F) -> TypeSchema<N> {
+        self.fold(&mut MapVars(f))
+    }
+}
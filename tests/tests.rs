@@ -295,7 +295,7 @@ fn test_merge_no_sacreds() {
     let mut last = ctx2.new_variable();
     assert_eq!(t.apply(&ctx2).to_string(), "bool → t2");
 
-    let ctx_change = ctx.merge(ctx2, vec![]);
+    let ctx_change = ctx.merge(ctx2, vec![]).expect("no conflict");
     ctx_change.reify_type(&mut t);
     assert_eq!(t.to_string(), "t4 → t5");
     assert_eq!(t.apply(&ctx).to_string(), "bool → t5");
@@ -321,7 +321,7 @@ fn test_merge_with_sacreds() {
     let mut last = ctx2.new_variable();
     assert_eq!(t.apply(&ctx2).to_string(), "t1 → bool");
 
-    let ctx_change = ctx.merge(ctx2, vec![0, 1]);
+    let ctx_change = ctx.merge(ctx2, vec![0, 1]).expect("no conflict");
     ctx_change.reify_type(&mut t);
     assert_eq!(t.to_string(), "t1 → t5");
     assert_eq!(t.apply(&ctx).to_string(), "bool → bool");
@@ -368,3 +368,1514 @@ fn test_parse() {
     );
     assert_eq!(t, Type::parse(&t.to_string()).expect("parse 12"));
 }
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_parse_named() {
+    let (t, names) = TypeSchema::<&'static str>::parse_named("∀a b. (a → b) → list(a) → list(b)")
+        .expect("parse 1");
+    assert_eq!(
+        t,
+        ptp!(0, 1; @arrow[
+            tp!(@arrow[tp!(0), tp!(1)]),
+            tp!(list(tp!(0))),
+            tp!(list(tp!(1))),
+        ])
+    );
+    assert_eq!(
+        names.into_iter().collect::<Vec<_>>(),
+        vec![("a".to_string(), 0), ("b".to_string(), 1)]
+    );
+
+    // named variables are numbered after explicit ones
+    let (t, names) =
+        TypeSchema::<&'static str>::parse_named("∀a. ∀t3. a → t3 → t1").expect("parse 2");
+    assert_eq!(t, ptp!(4, 3; @arrow[tp!(4), tp!(3), tp!(1)]));
+    assert_eq!(names["a"], 4);
+
+    // names are constructors outside of their binders
+    let (t, names) = TypeSchema::<&'static str>::parse_named("list(a)").expect("parse 3");
+    assert_eq!(t, ptp!(list(tp!(a))));
+    assert!(names.is_empty());
+
+    // names bound by a failed alternative are forgotten
+    let (t, names) = TypeSchema::<&'static str>::parse_named("forallx").expect("parse 4");
+    assert_eq!(t, ptp!(forallx));
+    assert!(names.is_empty());
+
+    // closed schemas read back as they were shown
+    let t = ptp!(0, 1; @arrow[tp!(0), tp!(list(tp!(1))), tp!(0)]);
+    for &naming in &[
+        VariableNaming::Numbered,
+        VariableNaming::Latin,
+        VariableNaming::Greek,
+    ] {
+        let s = t.show_named(naming);
+        let (t2, _) = TypeSchema::<&'static str>::parse_named(&s).expect("parse named");
+        assert_eq!(t2, t);
+        assert_eq!(s, t2.show_named(naming));
+    }
+
+    // but free variables read back as constructors
+    let t = ptp!(5, 2; @arrow[tp!(5), tp!(list(tp!(2))), tp!(9)]);
+    let s = t.show_named(VariableNaming::Latin);
+    assert_eq!(s, "∀a b. a → list(b) → c");
+    let (t2, _) = TypeSchema::<&'static str>::parse_named(&s).expect("parse named");
+    assert_eq!(t2, ptp!(0, 1; @arrow[tp!(0), tp!(list(tp!(1))), tp!(c)]));
+}
+
+#[test]
+fn test_show_named() {
+    let t = Type::from((0..28).map(Type::Variable).collect::<Vec<Type>>());
+    let s = t.show_named(VariableNaming::Latin);
+    assert!(s.starts_with("a → b → c"));
+    assert!(s.ends_with("y → z → a1 → b1"));
+    let t = ptp!(1, 0; @arrow[tp!(0), tp!(1)]);
+    assert_eq!(t.show_named(VariableNaming::Greek), "∀α β. β → α");
+    assert_eq!(t.show_named(VariableNaming::Numbered), t.to_string());
+}
+
+#[test]
+fn test_display_with() {
+    let t = ptp!(0; @arrow[
+        tp!(@arrow[tp!(0), tp!(int), tp!(0)]),
+        tp!(0),
+        tp!(dict(tp!(str), tp!(list(tp!(int))))),
+        tp!(0),
+    ]);
+    assert_eq!(t.display_with(&PrettyConfig::default()), t.to_string());
+    let config = PrettyConfig {
+        width: Some(20),
+        naming: VariableNaming::Latin,
+        ..PrettyConfig::default()
+    };
+    assert_eq!(
+        t.display_with(&config),
+        "∀a.\n  (a → int → a)\n  → a\n  → dict(\n    str,\n    list(int)\n  )\n  → a"
+    );
+    // nothing fits in a tiny width, so every group breaks
+    let config = PrettyConfig {
+        width: Some(1),
+        ..PrettyConfig::default()
+    };
+    assert_eq!(
+        tp!(@arrow[tp!(@arrow[tp!(0), tp!(1)]), tp!(1)]).display_with(&config),
+        "(t0\n → t1)\n→ t1"
+    );
+}
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_display_with_round_trip() {
+    let t = ptp!(0, 1; @arrow[
+        tp!(@arrow[tp!(1), tp!(0), tp!(1)]),
+        tp!(1),
+        tp!(hashmap(tp!(str), tp!(list(tp!(0))))),
+        tp!(1),
+    ]);
+    for &width in &[None, Some(40), Some(10), Some(1)] {
+        let config = PrettyConfig {
+            ascii_arrows: true,
+            ascii_quantifiers: true,
+            comma_space: true,
+            width,
+            ..PrettyConfig::default()
+        };
+        let s = t.display_with(&config);
+        assert_eq!(TypeSchema::parse(&s).expect("round trip"), t);
+        let config = PrettyConfig {
+            naming: VariableNaming::Greek,
+            ..config
+        };
+        let s = t.display_with(&config);
+        let (t2, _) = TypeSchema::<&'static str>::parse_named(&s).expect("round trip named");
+        assert_eq!(t2, t);
+    }
+}
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_module() {
+    let module: Module = Module::parse(
+        "
+        constructor int
+        constructor pair(a, b)
+        type point = pair(int, int)
+        type endo(a) = a -> a
+        type path = list(point)
+
+        origin : point
+        translate : endo(point) # move a point
+        walk : path -> endo(point) -> path
+        swap : ∀a b. pair(a, b) -> pair(b, a)
+        ",
+    )
+    .expect("valid module");
+    let point = tp!(pair(tp!(int), tp!(int)));
+    assert_eq!(
+        module.signatures().keys().collect::<Vec<_>>(),
+        vec!["origin", "translate", "walk", "swap"]
+    );
+    assert_eq!(
+        module.signatures()["origin"],
+        TypeSchema::Monotype(point.clone())
+    );
+    assert_eq!(
+        module.signatures()["walk"],
+        ptp!(@arrow[
+            tp!(list(point.clone())),
+            tp!(@arrow[point.clone(), point.clone()]),
+            tp!(list(point.clone())),
+        ])
+    );
+    assert_eq!(
+        module.signatures()["swap"],
+        ptp!(0, 1; @arrow[tp!(pair(tp!(0), tp!(1))), tp!(pair(tp!(1), tp!(0)))])
+    );
+    assert_eq!(module.aliases().len(), 3);
+    assert_eq!(module.arities()["pair"], 2);
+
+    let errors = Module::<&'static str>::parse(
+        "constructor int\ntype int = bool\nf : int(bool)\nf : bool\nf : int\ng : ∀. int",
+    )
+    .expect_err("invalid module");
+    assert_eq!(
+        errors.iter().map(|e| e.to_string()).collect::<Vec<_>>(),
+        vec![
+            "line 2: int is already declared",
+            "line 3: int expects 0 argument(s) but was given 1",
+            "line 5: f is already declared",
+            "line 6: invalid type",
+        ]
+    );
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Sugared(&'static str);
+impl Name for Sugared {
+    fn arrow() -> Self {
+        Sugared("→")
+    }
+    fn show(&self) -> String {
+        self.0.to_string()
+    }
+    fn parse(s: &str) -> Result<Self, ParseError> {
+        <&'static str as Name>::parse(s).map(Sugared)
+    }
+    fn tuple() -> Option<Self> {
+        Some(Sugared("tuple"))
+    }
+    fn list() -> Option<Self> {
+        Some(Sugared("list"))
+    }
+}
+
+#[test]
+fn test_sugar_display() {
+    let int = || Type::Constructed(Sugared("int"), vec![]);
+    let t = Type::arrow(
+        Type::Constructed(
+            Sugared("list"),
+            vec![Type::Constructed(
+                Sugared("tuple"),
+                vec![int(), Type::Variable(0)],
+            )],
+        ),
+        Type::Constructed(
+            Sugared("tuple"),
+            vec![
+                Type::Constructed(Sugared("list"), vec![int()]),
+                int(),
+                int(),
+            ],
+        ),
+    );
+    assert_eq!(t.to_string(), "[(int,t0)] → ([int],int,int)");
+    let config = PrettyConfig {
+        comma_space: true,
+        ..PrettyConfig::default()
+    };
+    assert_eq!(t.display_with(&config), "[(int, t0)] → ([int], int, int)");
+    // sugar only applies with the right number of arguments
+    let t = Type::Constructed(
+        Sugared("list"),
+        vec![Type::Constructed(Sugared("tuple"), vec![int()]), int()],
+    );
+    assert_eq!(t.to_string(), "list(tuple(int),int)");
+}
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_sugar_parse() {
+    for s in &[
+        "[int]",
+        "(int,bool)",
+        "[(int,t0)] → ([int],int,int)",
+        "(int → t1,[t1 → bool]) → [[t1]]",
+        "list(tuple(int),int)",
+    ] {
+        let t = Type::<Sugared>::parse(s).expect("parses");
+        assert_eq!(&t.to_string(), s);
+    }
+    assert_eq!(
+        Type::<Sugared>::parse("( int ,  [ bool ] )").expect("parses"),
+        Type::parse("tuple(int, list(bool))").expect("parses")
+    );
+    // without sugar, neither form parses
+    Type::<&'static str>::parse("[int]").expect_err("no list sugar");
+    Type::<&'static str>::parse("(int, bool)").expect_err("no tuple sugar");
+}
+
+#[test]
+fn test_enumerator() {
+    let library = vec![
+        ("nil".to_string(), ptp!(0; list(tp!(0)))),
+        (
+            "cons".to_string(),
+            ptp!(0; @arrow[tp!(0), tp!(list(tp!(0))), tp!(list(tp!(0)))]),
+        ),
+        (
+            "map".to_string(),
+            ptp!(0, 1; @arrow[tp!(@arrow[tp!(0), tp!(1)]), tp!(list(tp!(0))), tp!(list(tp!(1)))]),
+        ),
+        ("zero".to_string(), ptp!(int)),
+    ];
+    let ctx = Context::default();
+    let goal = tp!(@arrow[tp!(list(tp!(int))), tp!(list(tp!(list(tp!(int)))))]);
+    let terms: Vec<Enumerated> = Enumerator::new(&library, &ctx, &goal, 4).collect();
+    // sizes never decrease
+    assert!(terms
+        .windows(2)
+        .all(|w| w[0].term.size() <= w[1].term.size()));
+    let shown: Vec<String> = terms.iter().map(|found| found.term.to_string()).collect();
+    assert_eq!(shown[0], "(λ #0)");
+    assert_eq!(terms[0].program, "(λ nil)");
+    assert!(!shown.contains(&String::from("(λ $0)")));
+    assert!(shown.contains(&String::from("(λ (#2 (λ #0) $0))")));
+    assert!(terms
+        .iter()
+        .any(|found| found.program == "(λ (map (λ nil) $0))"));
+    for found in &terms {
+        assert_eq!(found.tp, goal);
+        assert_eq!(
+            found.program,
+            found
+                .term
+                .display_with_names(&["nil", "cons", "map", "zero"])
+        );
+    }
+
+    // nothing inhabits a type with no primitives
+    let terms: Vec<_> = Enumerator::new(&library, &ctx, &tp!(bool), 5).collect();
+    assert!(terms.is_empty());
+}
+
+#[test]
+fn test_grammar_likelihood() {
+    let grammar = Grammar::new(
+        -1.0,
+        vec![
+            (0.0, ptp!(bool)),
+            (-0.5, ptp!(@arrow[tp!(int), tp!(bool)])),
+            (0.0, ptp!(int)),
+            (-2.0, ptp!(0; @arrow[tp!(bool), tp!(0), tp!(0), tp!(0)])),
+        ],
+    );
+    let library: Vec<(String, TypeSchema)> = grammar
+        .productions()
+        .iter()
+        .enumerate()
+        .map(|(i, (_, t))| (format!("p{}", i), t.clone()))
+        .collect();
+    let goal = tp!(@arrow[tp!(int), tp!(bool)]);
+    let ctx = Context::default();
+    // the probabilities of all terms up to some size approach but never exceed 1
+    let total: f64 = Enumerator::new(&library, &ctx, &goal, 7)
+        .map(|found| grammar.likelihood(&found.term, &goal).exp())
+        .sum();
+    assert!(total <= 1.0 + 1e-9);
+    assert!(total > 0.9);
+    // abstractions are required at arrow types
+    assert_eq!(
+        grammar.likelihood(&Term::Primitive(1), &goal),
+        f64::NEG_INFINITY
+    );
+    // primitives must be fully applied
+    assert_eq!(
+        grammar.likelihood(&Term::Abstraction(Box::new(Term::Primitive(3))), &goal),
+        f64::NEG_INFINITY
+    );
+}
+
+#[cfg(feature = "sampling")]
+#[test]
+fn test_grammar_sample() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let grammar = Grammar::new(
+        0.0,
+        vec![
+            (0.0, ptp!(bool)),
+            (0.0, ptp!(@arrow[tp!(int), tp!(bool)])),
+            (0.0, ptp!(int)),
+        ],
+    );
+    let goal = tp!(bool);
+    let mut rng = StdRng::seed_from_u64(42);
+    let samples: Vec<Term> = (0..2000)
+        .map(|_| grammar.sample(&goal, &mut rng).expect("sample"))
+        .collect();
+    let constants = samples.iter().filter(|t| **t == Term::Primitive(0)).count();
+    assert!((constants as f64 / 2000.0 - 0.5).abs() < 0.05);
+    for term in &samples {
+        assert!((grammar.likelihood(term, &goal) - 0.5f64.ln()).abs() < 1e-9);
+    }
+
+    // there is no way to make a str
+    assert_eq!(grammar.sample(&tp!(str), &mut rng), None);
+}
+
+#[cfg(all(feature = "sampling", feature = "parser"))]
+#[test]
+fn test_generated_types_round_trip() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let generator = TypeGenerator::default()
+        .with_max_size(20)
+        .with_reuse_probability(0.8);
+    let mut rng = StdRng::seed_from_u64(1);
+    for _ in 0..200 {
+        let t = generator.generate_type(&mut rng);
+        assert!(generator.is_well_kinded(&t));
+        assert_eq!(Type::parse(&t.to_string()).expect("parses"), t);
+        let t = generator.generate_schema(&mut rng);
+        assert_eq!(TypeSchema::parse(&t.to_string()).expect("parses"), t);
+        for smaller in generator.shrink_schema(&t) {
+            let mut body = &smaller;
+            while let TypeSchema::Polytype { body: ref b, .. } = *body {
+                body = b;
+            }
+            let body_vars = body.free_vars();
+            for v in smaller.bound_vars() {
+                assert!(t.bound_vars().contains(&v));
+                assert!(body_vars.contains(&v));
+            }
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "not in [0, 1]")]
+fn test_generator_rejects_invalid_probabilities() {
+    TypeGenerator::default().with_reuse_probability(1.5);
+}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary {
+    use polytype::{Type, TypeGenerator, TypeSchema};
+    use quickcheck::quickcheck;
+
+    quickcheck! {
+        fn arbitrary_types_are_well_kinded(t: Type) -> bool {
+            TypeGenerator::default().is_well_kinded(&t)
+        }
+        fn arbitrary_schemas_generalize(t: TypeSchema) -> bool {
+            t.free_vars().iter().all(|v| !t.is_bound(*v))
+        }
+    }
+}
+
+#[test]
+fn test_type_index() {
+    let mut index = TypeIndex::new();
+    let library = [
+        ptp!(int),
+        ptp!(0; list(tp!(0))),
+        ptp!(list(tp!(int))),
+        ptp!(0, 1; @arrow[tp!(0), tp!(1), tp!(0)]),
+        ptp!(0; @arrow[tp!(list(tp!(0))), tp!(int)]),
+    ];
+    for (i, t) in library.iter().enumerate() {
+        index.insert_schema(t, i);
+    }
+    assert_eq!(index.len(), 5);
+
+    assert_eq!(index.unifiable_with(&tp!(0)), vec![&0, &1, &2, &3, &4]);
+    assert_eq!(index.instances_of(&tp!(list(tp!(0)))), vec![&1, &2]);
+    assert_eq!(index.generalizations_of(&tp!(list(tp!(0)))), vec![&1]);
+    assert_eq!(
+        index.unifiable_with(&tp!(@arrow[tp!(list(tp!(bool))), tp!(int)])),
+        vec![&4]
+    );
+    // variables are not told apart, so this is a candidate but doesn't unify
+    let goal = tp!(@arrow[tp!(int), tp!(int), tp!(bool)]);
+    assert_eq!(index.unifiable_with(&goal), vec![&3]);
+    let mut ctx = Context::default();
+    let t = library[3].instantiate(&mut ctx);
+    assert!(ctx.unify(&t, &goal).is_err());
+}
+
+#[cfg(feature = "sampling")]
+#[test]
+fn test_type_index_finds_every_unifiable_type() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let generator = TypeGenerator::default().with_max_size(6);
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut index = TypeIndex::new();
+    let library: Vec<Type> = (0..200)
+        .map(|_| generator.generate_type(&mut rng))
+        .collect();
+    for (i, t) in library.iter().enumerate() {
+        index.insert(t, i);
+    }
+    for _ in 0..100 {
+        // keep the query's variables apart from the library's
+        let goal = generator.generate_type(&mut rng);
+        let shift = goal
+            .vars()
+            .into_iter()
+            .map(|v| (v, Type::Variable(v + 100)))
+            .collect();
+        let goal = goal.substitute(&shift);
+        let candidates = index.unifiable_with(&goal);
+        for (i, t) in library.iter().enumerate() {
+            let mut ctx = Context::default();
+            if ctx.unify(t, &goal).is_ok() {
+                assert!(candidates.contains(&&i), "{} missing for {}", t, goal);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_interner() {
+    let mut interner = TypeInterner::new();
+    let t = tp!(@arrow[tp!(0), tp!(list(tp!(0))), tp!(pair(tp!(int), tp!(1)))]);
+    let shared = interner.intern(&t);
+    assert_eq!(shared.to_type(), t);
+    assert_eq!(Type::from(&shared), t);
+    assert_eq!(shared, interner.intern(&t.clone()));
+    assert_ne!(shared, interner.intern(&tp!(0)));
+
+    let mut substitution = std::collections::HashMap::new();
+    substitution.insert(0, interner.intern(&tp!(bool)));
+    let substituted = interner.substitute(&shared, &substitution);
+    let mut expected = std::collections::HashMap::new();
+    expected.insert(0, tp!(bool));
+    assert_eq!(substituted.to_type(), t.substitute(&expected));
+    assert!(!substituted.is_ground());
+
+    // substituting nothing shares the whole type
+    let before = interner.len();
+    let unchanged = interner.substitute(&shared, &std::collections::HashMap::new());
+    assert_eq!(unchanged, shared);
+    assert_eq!(interner.len(), before);
+
+    let mut ctx = Context::default();
+    let schema = ptp!(0; @arrow[tp!(0), tp!(0)]);
+    let instantiated = interner.instantiate(&schema, &mut ctx);
+    ctx.unify(&instantiated.to_type(), &tp!(@arrow[tp!(int), tp!(0)]))
+        .expect("unifies");
+    assert_eq!(
+        interner.apply(&instantiated, &ctx).to_type(),
+        tp!(@arrow[tp!(int), tp!(int)])
+    );
+}
+
+#[test]
+fn test_solved_context() {
+    fn assert_sync<T: Sync + Send>() {}
+    assert_sync::<SolvedContext>();
+
+    let mut ctx = Context::default();
+    let a = ctx.new_variable();
+    let b = ctx.new_variable();
+    let d = ctx.new_variable();
+    ctx.unify(&a, &tp!(@arrow[b.clone(), b.clone()]))
+        .expect("unifies");
+    ctx.unify(&b, &tp!(list(d.clone()))).expect("unifies");
+    let solved = ctx.solved();
+    assert_eq!(solved.len(), 2);
+    assert_eq!(
+        solved.apply(&a),
+        tp!(@arrow[tp!(list(tp!(2))), tp!(list(tp!(2)))])
+    );
+    assert_eq!(solved.apply(&a), a.apply(&ctx));
+
+    // the main context moves on while a worker uses a fork
+    let c = ctx.new_variable();
+    let mut fork = solved.fork();
+    let mut x = fork.new_variable();
+    assert_eq!(x, tp!(3));
+    fork.unify(&x, &tp!(pair(tp!(int), d.clone())))
+        .expect("unifies");
+    fork.unify(&d, &tp!(bool)).expect("unifies");
+
+    let change = ctx.merge_fork(fork, &solved).expect("no conflict");
+    change.reify_type(&mut x);
+    assert_eq!(c, tp!(3));
+    assert_eq!(x, tp!(4));
+    assert_eq!(x.apply(&ctx), tp!(pair(tp!(int), tp!(bool))));
+    assert_eq!(a.apply(&ctx).to_string(), "list(bool) → list(bool)");
+    assert_eq!(ctx.new_variable(), tp!(5));
+}
+
+#[cfg(feature = "persistent")]
+#[test]
+fn test_persistent_context() {
+    let mut ctx = Context::default();
+    let a = ctx.new_variable();
+    let b = ctx.new_variable();
+    ctx.unify(&a, &tp!(list(b.clone()))).expect("unifies");
+
+    let pctx = PersistentContext::from(ctx.clone());
+    assert_eq!(pctx.apply(&a), a.apply(&ctx));
+
+    let bound = pctx.unify(&b, &tp!(int)).expect("unifies");
+    assert_eq!(bound.apply(&a), tp!(list(tp!(int))));
+    assert_eq!(pctx.apply(&a), tp!(list(tp!(1))));
+    assert_eq!(bound.len(), 2);
+    assert_eq!(pctx.len(), 1);
+
+    assert_eq!(
+        bound.unify(&a, &tp!(list(tp!(bool)))),
+        Err(UnificationError::Failure(tp!(int), tp!(bool)))
+    );
+    assert_eq!(pctx.unify(&b, &a), Err(UnificationError::Occurs(1)));
+
+    let mut back = Context::from(bound);
+    assert_eq!(a.apply(&back), tp!(list(tp!(int))));
+    assert_eq!(back.new_variable(), tp!(2));
+}
+
+#[test]
+fn test_deep_types() {
+    const DEPTH: usize = 100_000;
+
+    // t0 → t1 → … and int → int → …
+    let mut ctx = Context::default();
+    let vars: Vec<Type> = (0..DEPTH).map(|_| ctx.new_variable()).collect();
+    let t1 = Type::from(vars);
+    let t2 = Type::from(vec![tp!(int); DEPTH]);
+    assert_ne!(t1, t2);
+    ctx.unify(&t1, &t2).expect("unifies");
+    let applied = t1.apply(&ctx);
+    assert_eq!(applied, t2);
+    let mut applied_mut = t1.clone();
+    applied_mut.apply_mut(&ctx);
+    assert_eq!(applied_mut, t2);
+    let solved = ctx.solved().apply(&t1);
+    assert_eq!(solved, t2);
+    assert_eq!(t2.to_string(), vec!["int"; DEPTH].join(" → "));
+
+    // list(list(…list(x)…))
+    let x = ctx.new_variable();
+    let mut nested = x.clone();
+    for _ in 0..DEPTH {
+        nested = tp!(list(nested));
+    }
+    assert_eq!(ctx.unify(&x, &nested), Err(UnificationError::Occurs(DEPTH)));
+    let y = ctx.new_variable();
+    ctx.unify(&y, &nested).expect("unifies");
+    ctx.unify(&x, &tp!(int)).expect("unifies");
+    let mut substitution = std::collections::HashMap::new();
+    substitution.insert(DEPTH, tp!(int));
+    let substituted = nested.substitute(&substitution);
+    let resolved = y.apply(&ctx);
+    assert_eq!(resolved, substituted);
+    let solved_nested = ctx.solved().apply(&y);
+    assert_eq!(solved_nested, substituted);
+    #[cfg(feature = "persistent")]
+    {
+        let mut pctx = PersistentContext::default();
+        pctx.extend(DEPTH, tp!(int));
+        let persistent = pctx.apply(&nested);
+        assert_eq!(persistent, substituted);
+        dismantle(persistent);
+    }
+    assert_eq!(
+        substituted.to_string(),
+        format!("{}int{}", "list(".repeat(DEPTH), ")".repeat(DEPTH)),
+    );
+    let mut set = std::collections::HashSet::new();
+    set.insert(resolved.clone());
+    assert!(set.contains(&substituted));
+
+    let shown = format!("{:?}", nested);
+    let expected = format!(
+        "{}Variable({}){}",
+        r#"Constructed("list", ["#.repeat(DEPTH),
+        DEPTH,
+        "])".repeat(DEPTH),
+    );
+    assert_eq!(shown, expected);
+
+    // dropping a type recurses into its arguments, so take deep types apart by hand.
+    fn dismantle(t: Type) {
+        let mut stack = vec![t];
+        while let Some(t) = stack.pop() {
+            if let Type::Constructed(_, args) = t {
+                stack.extend(args)
+            }
+        }
+    }
+    set.into_iter()
+        .chain(vec![
+            t1,
+            t2,
+            applied,
+            applied_mut,
+            solved,
+            nested,
+            substituted,
+            resolved,
+            solved_nested,
+        ])
+        .for_each(dismantle);
+}
+
+#[test]
+fn test_visit_and_fold() {
+    #[derive(Default)]
+    struct Collect {
+        binders: Vec<Variable>,
+        bound: Vec<Variable>,
+        free: Vec<Variable>,
+        names: Vec<&'static str>,
+    }
+    impl TypeVisitor for Collect {
+        fn visit_constructed(&mut self, name: &&'static str, _args: &[Type]) {
+            self.names.push(name)
+        }
+        fn visit_variable(&mut self, v: Variable) {
+            self.free.push(v)
+        }
+        fn visit_binder(&mut self, v: Variable) {
+            self.binders.push(v)
+        }
+        fn visit_bound_variable(&mut self, v: Variable) {
+            self.bound.push(v)
+        }
+    }
+    let schema = ptp!(0, 2; @arrow[tp!(0), tp!(pair(tp!(1), tp!(2))), tp!(0)]);
+    let mut collect = Collect::default();
+    schema.walk(&mut collect);
+    assert_eq!(collect.binders, vec![0, 2]);
+    assert_eq!(collect.bound, vec![0, 2, 0]);
+    assert_eq!(collect.free, vec![1]);
+    assert_eq!(collect.names, vec!["→", "→", "pair"]);
+
+    // shift every variable, bound or free, by 10
+    struct Shift;
+    impl TypeFolder for Shift {
+        fn fold_variable(&mut self, v: Variable) -> Type {
+            Type::Variable(v + 10)
+        }
+        fn fold_binder(&mut self, v: Variable) -> Variable {
+            v + 10
+        }
+    }
+    assert_eq!(
+        schema.fold(&mut Shift),
+        ptp!(10, 12; @arrow[tp!(10), tp!(pair(tp!(11), tp!(12))), tp!(10)])
+    );
+    assert_eq!(
+        schema.map_vars(|v| tp!(list(Type::Variable(v)))),
+        ptp!(0, 2; @arrow[tp!(0), tp!(pair(tp!(list(tp!(1))), tp!(2))), tp!(0)])
+    );
+    assert_eq!(
+        schema.map_names(|&n| if n == "pair" { "tuple" } else { n }),
+        ptp!(0, 2; @arrow[tp!(0), tp!(tuple(tp!(1), tp!(2))), tp!(0)])
+    );
+
+    let t = tp!(@arrow[tp!(int), tp!(list(tp!(list(tp!(0)))))]);
+    assert_eq!(t.size(), 5);
+    assert_eq!(t.depth(), 4);
+    assert!(t.any(|t| *t == tp!(0)));
+    assert_eq!(
+        t.map_vars(|_| tp!(bool)),
+        tp!(@arrow[tp!(int), tp!(list(tp!(list(tp!(bool)))))])
+    );
+}
+
+#[test]
+fn test_map_name() {
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    struct Id(u32);
+    impl Name for Id {
+        fn arrow() -> Self {
+            Id(0)
+        }
+    }
+    let names = ["→", "int", "list"];
+    let to_id = |name: &&'static str| Id(names.iter().position(|n| n == name).unwrap() as u32);
+    let from_id = |id: &Id| names[id.0 as usize];
+
+    let t = tp!(@arrow[tp!(int), tp!(list(tp!(0)))]);
+    let mapped: Type<Id> = t.map_name(to_id);
+    assert!(mapped.as_arrow().is_some());
+    assert_eq!(mapped.map_name(from_id), t);
+
+    let schema = ptp!(0; @arrow[tp!(0), tp!(list(tp!(0)))]);
+    assert_eq!(schema.map_name(to_id).map_name(from_id), schema);
+
+    let mut ctx = Context::default();
+    let v = ctx.new_variable();
+    ctx.unify(&v, &tp!(list(tp!(int)))).expect("unifies");
+    let mut mapped_ctx: Context<Id> = ctx.map_name(to_id);
+    assert_eq!(
+        Type::Variable(0).apply(&mapped_ctx).map_name(from_id),
+        tp!(list(tp!(int)))
+    );
+    assert_eq!(mapped_ctx.new_variable(), Type::Variable(1));
+
+    // changes reify types with any name
+    let mut other: Context<Id> = Context::default();
+    let mut w = other.new_variable();
+    let change = mapped_ctx.merge(other, vec![]).expect("no conflict");
+    change.reify_type(&mut w);
+    assert_eq!(w, Type::Variable(2));
+}
+
+#[test]
+fn test_merge_shared_constraints() {
+    let mut ctx = Context::default();
+    let a = ctx.new_variable();
+    let b = ctx.new_variable();
+    ctx.unify(&a, &tp!(@arrow[b.clone(), tp!(int)])).unwrap();
+
+    // ctx2 constrains the shared t1, and t0 of its own
+    let mut ctx2 = Context::default();
+    let c = ctx2.new_variable();
+    let _ = ctx2.new_variable();
+    ctx2.unify(&c, &tp!(list(tp!(1)))).unwrap();
+    ctx2.extend(1, tp!(bool));
+
+    let mut schema = ptp!(0, 1; @arrow[tp!(0), tp!(1)]);
+    let change = ctx.merge(ctx2.clone(), vec![1]).expect("no conflict");
+    change.reify_typeschema(&mut schema);
+    assert_eq!(schema, ptp!(2, 1; @arrow[tp!(2), tp!(1)]));
+    assert_eq!(a.apply(&ctx).to_string(), "bool → int");
+    let mut c2 = c.clone();
+    change.reify_type(&mut c2);
+    assert_eq!(c2.apply(&ctx), tp!(list(tp!(bool))));
+
+    // a conflicting constraint leaves the context unaffected
+    let before = ctx.substitution().clone();
+    let mut ctx3 = Context::default();
+    let _ = ctx3.new_variable();
+    ctx3.extend(1, tp!(int));
+    assert_eq!(
+        ctx.merge(ctx3, vec![1]).err(),
+        Some(UnificationError::Failure(tp!(bool), tp!(int)))
+    );
+    assert_eq!(*ctx.substitution(), before);
+    assert_eq!(ctx.new_variable(), tp!(4));
+}
+
+#[test]
+fn test_substitution() {
+    let mut ctx = Context::default();
+    let t0 = ctx.new_variable();
+    let t1 = ctx.new_variable();
+    let t2 = ctx.new_variable();
+    ctx.unify(&t0, &tp!(list(t1.clone()))).expect("unifies");
+    ctx.unify(&t1, &tp!(int)).expect("unifies");
+
+    let s = Substitution::from(&ctx);
+    assert!(s.is_idempotent());
+    assert_eq!(s.domain(), vec![0, 1]);
+    assert_eq!(s.get(0), Some(&tp!(list(tp!(int)))));
+    let t = tp!(@arrow[t0.clone(), t2.clone()]);
+    assert_eq!(s.apply(&t), t.apply(&ctx));
+
+    // composition agrees with applying in sequence
+    let s2: Substitution = vec![(2, tp!(list(t0.clone()))), (3, tp!(bool))]
+        .into_iter()
+        .collect();
+    let t = tp!(@arrow[t0.clone(), t2.clone(), tp!(3)]);
+    assert_eq!(s2.compose(&s).apply(&t), s.apply(&s2.apply(&t)));
+    assert_eq!(s.compose(&s2).apply(&t), s2.apply(&s.apply(&t)));
+    assert_eq!(s2.range_vars(), vec![0]);
+    assert!(!s2.compose(&s).range_vars().contains(&0));
+
+    // a variable mapped back to itself is dropped
+    let swap: Substitution = vec![(0, tp!(1)), (1, tp!(0))].into_iter().collect();
+    let back = swap.compose(&swap);
+    assert!(back.is_empty());
+
+    assert_eq!(s2.restrict(&[3, 4]).domain(), vec![3]);
+
+    // binders shadow the substitution
+    let schema = ptp!(2; @arrow[tp!(2), tp!(0)]);
+    assert_eq!(
+        s.apply_to_schema(&schema),
+        ptp!(2; @arrow[tp!(2), tp!(list(tp!(int)))])
+    );
+
+    // confining to an unbound variable ignores it, and keeps what remains
+    // meaningful without the dropped substitutions
+    ctx.confine(&[0, 2]);
+    assert_eq!(ctx.len(), 1);
+    assert_eq!(t0.apply(&ctx), tp!(list(tp!(int))));
+}
+
+#[test]
+fn test_schema_substitution_avoids_capture() {
+    use std::collections::HashMap;
+
+    // ∀t0. ∀t1. t0 → t1 → t2, substituting t2 with t0 → t1
+    let t = ptp!(0, 1; @arrow[tp!(0), tp!(1), tp!(2)]);
+    let mut substitution = HashMap::new();
+    substitution.insert(2, tp!(@arrow[tp!(0), tp!(1)]));
+    substitution.insert(0, tp!(bool));
+    let s = t.substitute(&substitution);
+    assert_eq!(s.free_vars(), vec![0, 1]);
+    assert_eq!(s.bound_vars().len(), 2);
+    assert!(s.bound_vars().iter().all(|v| *v > 2));
+
+    // instances of the substituted schema are instances of the original
+    let mut ctx = Context::default();
+    let expected = tp!(@arrow[tp!(int), tp!(bool), tp!(@arrow[tp!(0), tp!(1)])]);
+    let _ = ctx.new_variable();
+    let _ = ctx.new_variable();
+    let inst = s.instantiate(&mut ctx);
+    ctx.unify(&inst, &expected).expect("unifies");
+
+    // substitutions that cannot capture leave binders alone
+    substitution.clear();
+    substitution.insert(2, tp!(list(tp!(3))));
+    assert_eq!(
+        t.substitute(&substitution),
+        ptp!(0, 1; @arrow[tp!(0), tp!(1), tp!(list(tp!(3)))])
+    );
+
+    // applying a context only affects free variables
+    let mut ctx = Context::default();
+    ctx.extend(0, tp!(int));
+    ctx.extend(2, tp!(list(tp!(0))));
+    assert_eq!(
+        t.apply(&ctx),
+        ptp!(0, 1; @arrow[tp!(0), tp!(1), tp!(list(tp!(int)))])
+    );
+}
+
+#[test]
+fn test_subtyping() {
+    let mut lattice = SubtypeLattice::new();
+    lattice.declare("nat", "int");
+    lattice.declare("int", "real");
+    lattice.set_variance("ref", vec![Variance::Invariant]);
+    assert!(lattice.is_subconstructor(&"nat", &"real"));
+    assert!(!lattice.is_subconstructor(&"real", &"nat"));
+
+    let mut ctx = Context::default();
+    let nat_to_real = tp!(@arrow[tp!(nat), tp!(real)]);
+    let int_to_int = tp!(@arrow[tp!(int), tp!(int)]);
+    assert!(ctx
+        .constrain_subtype(&lattice, &int_to_int, &nat_to_real)
+        .is_ok());
+    assert_eq!(
+        ctx.constrain_subtype(&lattice, &nat_to_real, &int_to_int),
+        Err(UnificationError::Failure(tp!(int), tp!(nat)))
+    );
+    assert!(ctx
+        .constrain_subtype(&lattice, &tp!(list(tp!(nat))), &tp!(list(tp!(real))))
+        .is_ok());
+    assert!(ctx
+        .constrain_subtype(&lattice, &tp!(ref(tp!(nat))), &tp!(ref(tp!(real))))
+        .is_err());
+
+    // bounds are propagated through variables
+    let a = ctx.new_variable();
+    let b = ctx.new_variable();
+    ctx.constrain_subtype(&lattice, &a, &b).expect("constrains");
+    ctx.constrain_subtype(&lattice, &b, &tp!(int))
+        .expect("constrains");
+    ctx.constrain_subtype(&lattice, &tp!(nat), &a)
+        .expect("constrains");
+    let before = ctx.clone();
+    assert!(ctx.constrain_subtype(&lattice, &tp!(real), &a).is_err());
+    assert_eq!(ctx, before);
+    assert_eq!(ctx.bounds(0).expect("bounds").lower, vec![tp!(nat)]);
+    assert_eq!(
+        ctx.bounds(1).expect("bounds").lower,
+        vec![a.clone(), tp!(nat)]
+    );
+    assert_eq!(
+        ctx.constrain_subtype(&lattice, &a, &tp!(list(a.clone()))),
+        Err(UnificationError::Occurs(0))
+    );
+
+    // λx. if c then x else 1, and λx. if c then 2.5 else 1
+    let x = ctx.new_variable();
+    let r = ctx.new_variable();
+    ctx.constrain_subtype(&lattice, &x, &r).expect("constrains");
+    ctx.constrain_subtype(&lattice, &tp!(int), &r)
+        .expect("constrains");
+    let t = tp!(@arrow[x.clone(), r.clone()]);
+    assert_eq!(
+        ctx.simplify_subtype(&lattice, &t).to_string(),
+        "∀t2. ∀t3. t2 → t3"
+    );
+    let s = ctx.new_variable();
+    ctx.constrain_subtype(&lattice, &tp!(real), &s)
+        .expect("constrains");
+    ctx.constrain_subtype(&lattice, &tp!(int), &s)
+        .expect("constrains");
+    let t = tp!(@arrow[tp!(bool), s]);
+    assert_eq!(
+        ctx.simplify_subtype(&lattice, &t).to_string(),
+        "bool → real"
+    );
+
+    // confining keeps the bounds of kept variables, applied
+    let c = ctx.new_variable();
+    let d = ctx.new_variable();
+    ctx.constrain_subtype(&lattice, &c, &tp!(list(d.clone())))
+        .expect("constrains");
+    ctx.unify(&d, &tp!(nat)).expect("unifies");
+    ctx.confine(&[5]);
+    assert_eq!(
+        ctx.bounds(5).expect("bounds").upper,
+        vec![tp!(list(tp!(nat)))]
+    );
+    assert_eq!(ctx.bounds(0), None);
+}
+
+#[test]
+fn test_effect_rows() {
+    let io = || tp!(io);
+    let rand = || tp!(rand);
+    let row = |labels, tail| Type::row(labels, tail);
+
+    // rows unify regardless of the order of their labels
+    let mut ctx = Context::default();
+    let e1 = ctx.new_variable();
+    let e2 = ctx.new_variable();
+    let t1 = Type::effect_arrow(tp!(int), row(vec![io(), rand()], None), tp!(str));
+    let t2 = Type::effect_arrow(tp!(int), row(vec![rand()], Some(e1.clone())), tp!(str));
+    ctx.unify(&t1, &t2).expect("unifies");
+    assert_eq!(e1.apply(&ctx), row(vec![io()], None));
+
+    // open rows share their tails
+    let t3 = Type::effect_arrow(tp!(int), row(vec![io()], Some(e2.clone())), tp!(str));
+    let e3 = ctx.new_variable();
+    let t4 = Type::effect_arrow(tp!(int), row(vec![tp!(fail(tp!(str)))], Some(e3)), tp!(str));
+    ctx.unify(&t3, &t4).expect("unifies");
+    assert_eq!(t3.apply(&ctx).to_string(), "int →{io,fail(str) | t4} str",);
+
+    // closed rows must have the same labels, and pure arrows have none
+    let mut ctx = Context::default();
+    assert!(ctx.unify(&t1, &tp!(@arrow[tp!(int), tp!(str)])).is_err());
+    let closed = Type::effect_arrow(tp!(int), row(vec![io()], None), tp!(str));
+    assert!(ctx.unify(&t1, &closed).is_err());
+    let e = ctx.new_variable();
+    let open = Type::effect_arrow(tp!(int), e.clone(), tp!(str));
+    ctx.unify(&open, &tp!(@arrow[tp!(int), tp!(str)]))
+        .expect("unifies");
+    assert_eq!(open.apply(&ctx).to_string(), "int → str");
+
+    // a row cannot contain itself
+    let mut ctx = Context::default();
+    let e = ctx.new_variable();
+    let r1 = Type::effect_arrow(tp!(int), row(vec![io()], Some(e.clone())), tp!(int));
+    let r2 = Type::effect_arrow(tp!(int), row(vec![rand()], Some(e)), tp!(int));
+    assert_eq!(ctx.unify(&r1, &r2), Err(UnificationError::Occurs(0)));
+    assert!(ctx.is_empty());
+
+    // display and parsing round-trip
+    for s in &[
+        "int →{io} str",
+        "(int →{io,rand | t0} str) → list(int) →{t0} int",
+        "∀t0. (int →{t0} str) → int →{fail(str) | t0} str",
+    ] {
+        let t: TypeSchema = TypeSchema::parse(s).expect("parses");
+        assert_eq!(&t.to_string(), s);
+    }
+    let t: TypeSchema = TypeSchema::parse("∀e. int ->{io | e} str").expect("parses");
+    assert_eq!(t.to_string(), "∀t0. int →{io | t0} str");
+    assert_eq!(
+        t.display_with(&PrettyConfig {
+            comma_space: true,
+            naming: VariableNaming::Greek,
+            ..PrettyConfig::default()
+        }),
+        "∀α. int →{io | α} str",
+    );
+    let t: Type = Type::parse("int →{} str").expect("parses");
+    assert_eq!(t, tp!(@arrow[tp!(int), tp!(str)]));
+
+    // the index matches arrows whatever their effects
+    let mut index = TypeIndex::new();
+    index.insert(&t1, "effectful");
+    index.insert(&tp!(@arrow[tp!(int), tp!(str)]), "pure");
+    assert_eq!(
+        index.unifiable_with(&tp!(@arrow[tp!(int), tp!(0)])),
+        vec![&"effectful", &"pure"]
+    );
+}
+
+#[test]
+fn test_units_of_measure() {
+    let dim = |factors: Vec<(Type, i32)>| Type::dimension(factors);
+
+    // float(m/s) and float(m·s^-1) unify however they are built
+    let mut ctx = Context::default();
+    let per_s = dim(vec![(tp!(s), -1)]);
+    let m_per_s = dim(vec![(tp!(m), 1), (per_s, 1)]);
+    let t1 = tp!(float(m_per_s));
+    let t2 = tp!(float(dim(vec![(tp!(s), -1), (tp!(m), 1)])));
+    assert_eq!(t1.to_string(), "float(m·s^-1)");
+    ctx.unify(&t1, &t2).expect("unifies");
+    assert!(ctx.is_empty());
+    assert!(ctx.unify(&t1, &tp!(float(dim(vec![(tp!(m), 1)])))).is_err());
+
+    // solving for a dimension variable
+    let u = ctx.new_variable();
+    let acceleration = dim(vec![(tp!(m), 1), (tp!(s), -2)]);
+    let t3 = dim(vec![(u.clone(), 1), (tp!(s), -1)]);
+    ctx.unify(&t3, &acceleration).expect("unifies");
+    assert_eq!(u.apply(&ctx).to_string(), "m·s^-1");
+    assert_eq!(t3.apply(&ctx).to_string(), "m·s^-2");
+
+    // exponents that do not divide need fresh variables
+    let mut ctx = Context::default();
+    let a = ctx.new_variable();
+    let b = ctx.new_variable();
+    let t4 = dim(vec![(a.clone(), 2), (b.clone(), 3)]);
+    ctx.unify(&t4, &dim(vec![(tp!(m), 1)])).expect("unifies");
+    assert_eq!(t4.apply(&ctx).to_string(), "m");
+    let mut ctx = Context::default();
+    let a = ctx.new_variable();
+    let t5 = dim(vec![(a, 2)]);
+    assert!(ctx.unify(&t5, &dim(vec![(tp!(m), 3)])).is_err());
+    assert!(ctx.unify(&t5, &dim(vec![])).is_ok());
+    assert_eq!(t5.apply(&ctx).to_string(), "1");
+
+    // exponents are kept as numbers, and extreme ones do not overflow
+    let mut ctx = Context::default();
+    let big = dim(vec![(tp!(m), 1_000_000_000), (tp!(s), i32::MIN)]);
+    assert_eq!(big.to_string(), "m^1000000000·s^-2147483648");
+    let u = ctx.new_variable();
+    ctx.unify(
+        &dim(vec![(u.clone(), i32::MIN)]),
+        &dim(vec![(tp!(s), i32::MIN)]),
+    )
+    .expect("unifies");
+    assert_eq!(u.apply(&ctx).to_string(), "s");
+    let u = ctx.new_variable();
+    assert!(ctx
+        .unify(&dim(vec![(u, -1)]), &dim(vec![(tp!(s), i32::MIN)]))
+        .is_err());
+
+    // generalization over dimension variables
+    let mut ctx = Context::default();
+    let u = ctx.new_variable();
+    let sqr = tp!(@arrow[
+        tp!(float(u.clone())),
+        tp!(float(dim(vec![(u, 2)]))),
+    ]);
+    let schema = sqr.generalize(&[]);
+    assert_eq!(schema.to_string(), "∀t0. float(t0) → float(t0^2)");
+    assert_eq!(
+        schema.display_with(&PrettyConfig {
+            naming: VariableNaming::Greek,
+            ..PrettyConfig::default()
+        }),
+        "∀α. float(α) → float(α^2)",
+    );
+}
+
+#[test]
+fn test_type_level_naturals() {
+    let sum = |terms: Vec<Type>, k| Type::nat_sum(terms, k);
+
+    // linear expressions unify once normalized
+    let mut ctx = Context::default();
+    let n = ctx.new_variable();
+    let m = ctx.new_variable();
+    let t1 = sum(vec![n.clone(), sum(vec![m.clone()], 1)], 1);
+    let t2 = sum(vec![m.clone(), n.clone()], 2);
+    assert_eq!(t1.to_string(), "t0+t1+2");
+    ctx.unify(&t1, &t2).expect("unifies");
+    assert!(ctx.is_empty());
+
+    // simple equations are solved
+    let t3 = sum(vec![n.clone()], 1);
+    ctx.unify(&t3, &sum(vec![m.clone()], 3)).expect("unifies");
+    assert_eq!(n.apply(&ctx).to_string(), "t1+2");
+    let mut ctx = Context::default();
+    let t4 = sum(vec![n.clone(), n.clone()], 1);
+    ctx.unify(&t4, &Type::nat(7)).expect("unifies");
+    assert_eq!(n.apply(&ctx), Type::nat(3));
+    let t5 = sum(vec![n.clone(), m.clone()], 0);
+    ctx.unify(&t5, &Type::nat(3)).expect("unifies");
+    assert_eq!(m.apply(&ctx), Type::nat(0));
+
+    // contradictions, and equations beyond linear solving
+    let mut ctx = Context::default();
+    assert_eq!(
+        ctx.unify(&t3, &Type::nat(0)),
+        Err(UnificationError::Failure(t3.clone(), Type::nat(0)))
+    );
+    assert!(ctx.unify(&t4, &Type::nat(4)).is_err());
+    assert_eq!(
+        ctx.unify(&t5, &Type::nat(3)),
+        Err(UnificationError::Unsolvable(t5.clone(), Type::nat(3)))
+    );
+    assert_eq!(
+        UnificationError::Unsolvable(t5, Type::nat(3)).to_string(),
+        "Unsolvable(t0+t1, 3)"
+    );
+    assert!(ctx.is_empty());
+
+    // sized containers
+    let append = ptp!(0, 1, 2; @arrow[
+        tp!(vec(tp!(0), tp!(2))),
+        tp!(vec(tp!(1), tp!(2))),
+        tp!(vec(sum(vec![tp!(0), tp!(1)], 0), tp!(2))),
+    ]);
+    let snoc = ptp!(0, 1; @arrow[
+        tp!(vec(tp!(0), tp!(1))),
+        tp!(1),
+        tp!(vec(sum(vec![tp!(0)], 1), tp!(1))),
+    ]);
+    let mut ctx = Context::default();
+    let append = append.instantiate(&mut ctx);
+    let snoc = snoc.instantiate(&mut ctx);
+    let xs = tp!(vec(Type::nat(2), tp!(int)));
+    let ret = ctx.new_variable();
+    ctx.unify(&snoc, &tp!(@arrow[xs.clone(), tp!(int), ret.clone()]))
+        .expect("unifies");
+    let ret2 = ctx.new_variable();
+    ctx.unify(&append, &tp!(@arrow[ret.clone(), xs, ret2.clone()]))
+        .expect("unifies");
+    assert_eq!(ret2.apply(&ctx).to_string(), "vec(5,int)");
+}
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_type_level_naturals_parse() {
+    for s in &[
+        "∀t0. ∀t1. ∀t2. vec(t0,t2) → vec(t1,t2) → vec(t0+t1,t2)",
+        "∀t0. vec(t0,int) → vec(2t0+1,int)",
+        "vec(3,int)",
+        "∀t0. t0+1 → vec(t0+1,int)",
+        "3 → int",
+    ] {
+        let t: TypeSchema = TypeSchema::parse(s).expect("parses");
+        assert_eq!(&t.to_string(), s);
+    }
+    let t: Type = Type::parse("2t0 -> int").expect("parses");
+    assert_eq!(
+        t,
+        tp!(@arrow[Type::nat_sum(vec![tp!(0), tp!(0)], 0), tp!(int)])
+    );
+    let t: TypeSchema =
+        TypeSchema::parse("∀n m. vec(n, a) → vec(m, a) → vec(n + m, a)").expect("parses");
+    assert_eq!(
+        t.to_string(),
+        "∀t0. ∀t1. vec(t0,a) → vec(t1,a) → vec(t0+t1,a)"
+    );
+    let t: Type = Type::parse("vec(t0 + 1, int)").expect("parses");
+    assert_eq!(t, tp!(vec(Type::nat_sum(vec![tp!(0)], 1), tp!(int))));
+    let t: Type = Type::parse("vec(t0, int)").expect("parses");
+    assert_eq!(t, tp!(vec(tp!(0), tp!(int))));
+    // constants and coefficients are kept as numbers, not counted out
+    let t: Type = Type::parse("vec(100000000000,int)").expect("parses");
+    assert_eq!(t, tp!(vec(Type::nat(100_000_000_000), tp!(int))));
+    assert_eq!(t.to_string(), "vec(100000000000,int)");
+    let t: Type = Type::parse("vec(100000000000t0+1,int)").expect("parses");
+    assert_eq!(t.to_string(), "vec(100000000000t0+1,int)");
+    let mut ctx = Context::default();
+    ctx.unify(&t, &tp!(vec(Type::nat(200_000_000_001), tp!(int))))
+        .expect("unifies");
+    assert_eq!(tp!(0).apply(&ctx), Type::nat(2));
+}
+
+#[test]
+fn test_gradual_typing() {
+    let dynamic = Type::dynamic;
+
+    // consistency is not equality, nor transitive
+    assert!(dynamic().is_consistent(&tp!(int)));
+    assert!(tp!(list(dynamic())).is_consistent(&tp!(list(tp!(bool)))));
+    assert!(dynamic().is_consistent(&tp!(bool)));
+    assert!(!tp!(int).is_consistent(&tp!(bool)));
+    assert!(!tp!(0).is_consistent(&tp!(int)));
+
+    let mut ctx = Context::default();
+    let t = tp!(@arrow[dynamic(), tp!(0)]);
+    assert!(ctx.unify(&t, &tp!(@arrow[tp!(int), tp!(bool)])).is_err());
+    ctx.set_gradual(true);
+    assert!(ctx.is_gradual());
+    ctx.unify(&t, &tp!(@arrow[tp!(int), tp!(bool)]))
+        .expect("consistent");
+    assert_eq!(t.apply(&ctx).to_string(), "? → bool");
+    assert_eq!(
+        ctx.casts(),
+        &[Cast {
+            from: tp!(int),
+            to: dynamic(),
+        }]
+    );
+
+    // failures record no casts
+    let mut ctx = Context::default();
+    ctx.set_gradual(true);
+    let t1 = tp!(pair(tp!(int), dynamic()));
+    assert!(ctx.unify(&tp!(pair(dynamic(), tp!(int))), &t1).is_ok());
+    assert_eq!(ctx.take_casts().len(), 2);
+    assert!(ctx
+        .unify(
+            &tp!(pair(dynamic(), tp!(bool))),
+            &tp!(pair(tp!(int), tp!(int)))
+        )
+        .is_err());
+    assert!(ctx.casts().is_empty());
+
+    // casts at each application of `map succ json_list`
+    let map = ptp!(0, 1; @arrow[
+        tp!(@arrow[tp!(0), tp!(1)]),
+        tp!(list(tp!(0))),
+        tp!(list(tp!(1))),
+    ]);
+    let succ = tp!(@arrow[tp!(int), tp!(int)]);
+    let json_list = tp!(list(dynamic()));
+    let mut ctx = Context::default();
+    ctx.set_gradual(true);
+    let map = map.instantiate(&mut ctx);
+    let ret1 = ctx.new_variable();
+    ctx.unify(&map, &tp!(@arrow[succ, ret1.clone()]))
+        .expect("consistent");
+    assert!(ctx.take_casts().is_empty());
+    let ret2 = ctx.new_variable();
+    ctx.unify(&ret1, &tp!(@arrow[json_list, ret2.clone()]))
+        .expect("consistent");
+    let casts: Vec<_> = ctx
+        .take_casts()
+        .into_iter()
+        .map(|cast| (cast.from.apply(&ctx), cast.to.apply(&ctx)))
+        .collect();
+    assert_eq!(casts, vec![(dynamic(), tp!(int))]);
+    assert_eq!(ret2.apply(&ctx).to_string(), "list(int)");
+
+    // merging carries casts over, reified, unless the merge fails
+    let mut ctx = Context::default();
+    ctx.set_gradual(true);
+    let a = ctx.new_variable();
+    let b = ctx.new_variable();
+    ctx.unify(&a, &tp!(list(tp!(int)))).expect("unifies");
+    ctx.unify(&b, &tp!(int)).expect("unifies");
+    let mut other = Context::default();
+    other.set_gradual(true);
+    let c = other.new_variable();
+    other.unify(&dynamic(), &tp!(list(c))).expect("consistent");
+    ctx.merge(other, vec![]).expect("merges");
+    assert_eq!(
+        ctx.take_casts(),
+        vec![Cast {
+            from: tp!(list(tp!(2))),
+            to: dynamic(),
+        }]
+    );
+    let mut other = Context::default();
+    let a = other.new_variable();
+    let b = other.new_variable();
+    other.unify(&a, &tp!(list(dynamic()))).expect("unifies");
+    other.unify(&b, &tp!(bool)).expect("unifies");
+    assert!(ctx.merge(other, vec![0, 1]).is_err());
+    assert!(ctx.casts().is_empty());
+
+    // forks of a gradual context are gradual
+    let solved = ctx.solved();
+    let mut fork = solved.fork();
+    assert!(fork.is_gradual());
+    let d = fork.new_variable();
+    fork.unify(&d, &tp!(pair(dynamic(), tp!(bool))))
+        .expect("unifies");
+    fork.unify(&tp!(pair(tp!(int), tp!(bool))), &d)
+        .expect("consistent");
+    assert_eq!(fork.casts().len(), 1);
+    ctx.merge_fork(fork, &solved).expect("merges");
+    assert_eq!(
+        ctx.take_casts(),
+        vec![Cast {
+            from: dynamic(),
+            to: tp!(int),
+        }]
+    );
+}
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_gradual_typing_parse() {
+    let t: TypeSchema = TypeSchema::parse("∀t0. (? → t0) → list(?) → ?").expect("parses");
+    assert_eq!(t.to_string(), "∀t0. (? → t0) → list(?) → ?");
+    let t: Type = Type::parse("? -> int").expect("parses");
+    assert_eq!(t, tp!(@arrow[Type::dynamic(), tp!(int)]));
+}
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_partial_signatures() {
+    let mut ctx = Context::default();
+    let _ = ctx.new_variable();
+    let sig = PartialSignature::parse("(_ → t0) → list(_) → _", &mut ctx).expect("parses");
+    assert_eq!(sig.wildcards(), &[1, 2, 3]);
+    assert_eq!(sig.signature().to_string(), "(t1 → t0) → list(t2) → t3");
+    assert_eq!(sig.to_string(), "(_ → t0) → list(_) → _");
+
+    // wildcards are solved by unification
+    assert_eq!(
+        sig.display_solved(&ctx, &PrettyConfig::default()),
+        "(_ → t0) → list(_) → _"
+    );
+    let map = ptp!(0, 1; @arrow[
+        tp!(@arrow[tp!(0), tp!(1)]),
+        tp!(list(tp!(0))),
+        tp!(list(tp!(1))),
+    ]);
+    let map = map.instantiate(&mut ctx);
+    ctx.unify(&map, sig.signature()).expect("unifies");
+    assert_eq!(sig.resolved(&ctx), vec![tp!(2), tp!(2), tp!(list(tp!(0)))]);
+    assert_eq!(
+        sig.display_solved(&ctx, &PrettyConfig::default()),
+        "(t2 → t0) → list(t2) → list(t0)"
+    );
+    ctx.unify(&tp!(2), &tp!(int)).expect("unifies");
+    assert_eq!(
+        sig.display_solved(
+            &ctx,
+            &PrettyConfig {
+                naming: VariableNaming::Greek,
+                ..PrettyConfig::default()
+            }
+        ),
+        "(int → α) → list(int) → list(α)"
+    );
+
+    // wildcards are only allowed in partial signatures
+    assert!(Type::<&str>::parse("_").is_err());
+    assert!(PartialSignature::parse("→ _", &mut ctx).is_err());
+}
+
+#[test]
+fn test_typed_holes() {
+    let mut ctx = Context::default();
+    let mut holes = TypedHoles::new();
+    let x = ctx.new_variable();
+    let scope = vec![
+        ("id".to_string(), ptp!(0; @arrow[tp!(0), tp!(0)])),
+        ("x".to_string(), TypeSchema::Monotype(x.clone())),
+        ("not".to_string(), ptp!(@arrow[tp!(bool), tp!(bool)])),
+        ("zero".to_string(), ptp!(int)),
+    ]
+    .into();
+
+    // λx. if ?cond then ?a else x
+    let cond = holes.hole("cond", &mut ctx, &scope);
+    let a = holes.hole("a", &mut ctx, &scope);
+    assert_eq!(holes.len(), 2);
+    ctx.unify(&cond, &tp!(bool)).expect("unifies");
+    ctx.unify(&a, &x).expect("unifies");
+
+    let reports = holes.report(&ctx);
+    assert_eq!(reports[0].expected, tp!(bool));
+    let names = |report: &HoleReport| -> Vec<String> {
+        report.candidates.iter().map(|c| c.name.clone()).collect()
+    };
+    assert_eq!(names(&reports[0]), vec!["x"]);
+    assert_eq!(reports[1].expected, x);
+    assert_eq!(names(&reports[1]), vec!["not", "id", "zero", "x"]);
+
+    // the expected types are only known once inference is done
+    ctx.unify(&x, &tp!(@arrow[tp!(bool), tp!(bool)]))
+        .expect("unifies");
+    let reports = holes.report(&ctx);
+    assert!(reports[0].candidates.is_empty());
+    assert_eq!(reports[1].expected.to_string(), "bool → bool");
+    assert_eq!(names(&reports[1]), vec!["x", "not", "id"]);
+    let id = &reports[1].candidates[2];
+    assert_eq!(id.schema.to_string(), "∀t0. t0 → t0");
+    assert_eq!(id.instance.to_string(), "bool → bool");
+}
+
+#[cfg(feature = "parser")]
+#[test]
+fn test_data_declarations() {
+    let tree: DataDecl = DataDecl::parse("data tree(a) = leaf | node(tree(a), a, tree(a))")
+        .expect("valid declaration");
+    assert_eq!(tree.arity(), 1);
+    assert_eq!(tree.tp(), tp!(tree(tp!(0))));
+    let schemas = tree.constructor_schemas();
+    assert_eq!(schemas[0], ("leaf".to_string(), ptp!(0; tree(tp!(0)))));
+    assert_eq!(
+        schemas[1].1.to_string(),
+        "∀t0. tree(t0) → t0 → tree(t0) → tree(t0)"
+    );
+    assert_eq!(
+        tree.eliminator().to_string(),
+        "∀t0. ∀t1. tree(t0) → t1 → (tree(t0) → t0 → tree(t0) → t1) → t1"
+    );
+    assert_eq!(
+        tree.fold().to_string(),
+        "∀t0. ∀t1. tree(t0) → t1 → (t1 → t0 → t1 → t1) → t1"
+    );
+
+    // without parameters, the result is the only variable
+    let bool_: DataDecl = DataDecl::parse("data boolean = yes | no").expect("valid declaration");
+    assert_eq!(
+        bool_.eliminator().to_string(),
+        "∀t0. boolean → t0 → t0 → t0"
+    );
+
+    assert!(DataDecl::<&'static str>::parse("maybe(a) = none | some(a)").is_err());
+    assert!(DataDecl::<&'static str>::parse("data maybe(a) = none | a").is_err());
+    assert!(DataDecl::<&'static str>::parse("data maybe(a) = none | some(t0)").is_err());
+
+    let module: Module = Module::parse(
+        "
+        type lookup(a, b) = a → b
+        data table(k, v) = empty | entry(lookup(k, v), table(k, v))
+        ",
+    )
+    .expect("valid module");
+    assert_eq!(module.arities()["table"], 2);
+    assert_eq!(
+        module.signatures()["entry"].to_string(),
+        "∀t0. ∀t1. (t0 → t1) → table(t0,t1) → table(t0,t1)"
+    );
+    assert_eq!(
+        module.data_types()["table"].fold().to_string(),
+        "∀t0. ∀t1. ∀t2. table(t0,t1) → t2 → ((t0 → t1) → t2 → t2) → t2"
+    );
+
+    let errors = Module::<&'static str>::parse(
+        "
+        data list(a) = nil | cons(a, list(a, a))
+        data maybe(a) = none | some(a)
+        data option(a) = none | some(a)
+        ",
+    )
+    .expect_err("invalid module");
+    assert_eq!(
+        errors,
+        vec![
+            ModuleError {
+                line: 2,
+                kind: ModuleErrorKind::Arity {
+                    name: String::from("list"),
+                    expected: 1,
+                    found: 2,
+                },
+            },
+            ModuleError {
+                line: 4,
+                kind: ModuleErrorKind::Duplicate(String::from("none")),
+            },
+        ]
+    );
+}